@@ -10,7 +10,7 @@ fn read_input() -> String {
 }
 
 fn wrap_position(position: i32) -> i32 {
-    ((position % 100) + 100) % 100
+    position.rem_euclid(100)
 }
 
 fn parse_line(line: &str) -> (char, i32) {
@@ -59,17 +59,13 @@ impl Dial {
         // Check if remainder movement crosses zero
         let mut remainder_crossing = 0;
         match direction {
-            'R' => {
-                // Moving right: cross 0 if old_pos + remainder >= 100
-                if old_position + remainder >= 100 {
-                    remainder_crossing = 1;
-                }
+            // Moving right: cross 0 if old_pos + remainder >= 100
+            'R' if old_position + remainder >= 100 => {
+                remainder_crossing = 1;
             }
-            'L' => {
-                // Moving left: cross 0 if old_pos - remainder < 0
-                if old_position <= remainder {
-                    remainder_crossing = 1;
-                }
+            // Moving left: cross 0 if old_pos - remainder < 0
+            'L' if old_position <= remainder => {
+                remainder_crossing = 1;
             }
             _ => {}
         }
@@ -44,7 +44,7 @@ fn is_repeating_pattern(n: u64) -> bool {
     let len = s.len(); // Get length
 
     // Must have even length to split in half
-    if len % 2 != 0 {
+    if !len.is_multiple_of(2) {
         return false;
     }
 
@@ -59,7 +59,7 @@ fn is_repeating_multiple(n: u64) -> bool {
     let len = s.len(); // Get length
 
     for pattern_len in 1..=len / 2 {
-        if len % pattern_len != 0 {
+        if !len.is_multiple_of(pattern_len) {
             continue;
         }
 
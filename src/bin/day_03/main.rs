@@ -34,6 +34,7 @@ fn largest_two_digit(line: &str) -> (u32, u32, u32) {
 }
 
 // Part 2, find 12 digit number
+#[allow(clippy::needless_range_loop)] // Index loop reads clearer here
 fn largest_n_digit(line: &str, n: usize) -> (Vec<u32>, u64) {
     let digits: Vec<u32> = line.chars().filter_map(|c| c.to_digit(10)).collect();
 
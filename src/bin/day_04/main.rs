@@ -13,7 +13,7 @@ fn parse_grid(input: &str) -> Vec<Vec<char>> {
     input.lines().map(|line| line.chars().collect()).collect()
 }
 
-fn count_neighbors(grid: &Vec<Vec<char>>, row: usize, col: usize) -> usize {
+fn count_neighbors(grid: &[Vec<char>], row: usize, col: usize) -> usize {
     let rows = grid.len() as i32;
     let cols = grid[0].len() as i32;
     let mut count = 0;
@@ -35,10 +35,13 @@ fn count_neighbors(grid: &Vec<Vec<char>>, row: usize, col: usize) -> usize {
         let new_col = col as i32 + dc;
 
         // Check bounds
-        if new_row >= 0 && new_row < rows && new_col >= 0 && new_col < cols {
-            if grid[new_row as usize][new_col as usize] == '@' {
-                count += 1;
-            }
+        if new_row >= 0
+            && new_row < rows
+            && new_col >= 0
+            && new_col < cols
+            && grid[new_row as usize][new_col as usize] == '@'
+        {
+            count += 1;
         }
     }
 
@@ -47,7 +50,7 @@ fn count_neighbors(grid: &Vec<Vec<char>>, row: usize, col: usize) -> usize {
 
 // Part 2, removing the found @s
 
-fn remove_pass(grid: &mut Vec<Vec<char>>) -> usize {
+fn remove_pass(grid: &mut [Vec<char>]) -> usize {
     let rows = grid.len();
     let cols = grid[0].len();
 
@@ -57,7 +60,7 @@ fn remove_pass(grid: &mut Vec<Vec<char>>) -> usize {
     for row in 0..rows {
         for col in 0..cols {
             if grid[row][col] == '@' {
-                let neighbors = count_neighbors(&grid, row, col);
+                let neighbors = count_neighbors(grid, row, col);
                 if neighbors < 4 {
                     to_remove.push((row, col));
                 }
@@ -73,7 +76,7 @@ fn remove_pass(grid: &mut Vec<Vec<char>>) -> usize {
     to_remove.len()
 }
 
-fn part_2(grid: &mut Vec<Vec<char>>) -> usize {
+fn part_2(grid: &mut [Vec<char>]) -> usize {
     let mut total_removed = 0;
     let mut pass = 1;
 
@@ -1,4 +1,5 @@
-use std::fs::read_to_string;
+use std::fs::{File, read_to_string};
+use std::io::{self, BufRead, BufReader};
 
 // Helper functions
 fn read_input() -> String {
@@ -9,40 +10,81 @@ fn read_input() -> String {
         .expect("Could not find input.txt")
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 struct Range {
     start: u64,
     end: u64,
 }
 
 impl Range {
-    fn parse(s: &str) -> Range {
-        let (start, end) = s.split_once('-').unwrap();
-        Range {
-            start: start.parse().unwrap(),
-            end: end.parse().unwrap(),
+    // Accepts inclusive "a-b" (or "a..=b") and half-open "a..b"
+    fn parse(s: &str) -> Result<Range, String> {
+        let s = s.trim();
+        let (start, end, half_open) = if let Some((a, b)) = s.split_once("..=") {
+            (a, b, false)
+        } else if let Some((a, b)) = s.split_once("..") {
+            (a, b, true)
+        } else if let Some((a, b)) = s.split_once('-') {
+            (a, b, false)
+        } else {
+            return Err(format!("'{}' is not a range (expected a-b or a..b)", s));
+        };
+
+        let start: u64 = parse_id(start)?;
+        let end: u64 = parse_id(end)?;
+
+        if half_open {
+            if end <= start {
+                return Err(format!("'{}' is empty or reversed", s));
+            }
+            return Ok(Range {
+                start,
+                end: end - 1,
+            });
         }
+        if end < start {
+            return Err(format!("'{}' is reversed (start > end)", s));
+        }
+        Ok(Range { start, end })
     }
 
     fn contains(&self, value: u64) -> bool {
         value >= self.start && value <= self.end
     }
+
+    // Number of IDs, u128 so 0-u64::MAX still fits
+    fn len(&self) -> u128 {
+        (self.end - self.start) as u128 + 1
+    }
+}
+
+fn parse_id(s: &str) -> Result<u64, String> {
+    s.trim()
+        .parse()
+        .map_err(|e| format!("invalid ID '{}': {}", s.trim(), e))
 }
 
-fn parse_input(input: &str) -> (Vec<Range>, Vec<u64>) {
+fn parse_input(input: &str) -> Result<(Vec<Range>, Vec<u64>), String> {
     let mut parts = input.split("\n\n");
 
-    let ranges: Vec<Range> = parts.next().unwrap().lines().map(Range::parse).collect();
+    let ranges: Vec<Range> = parts
+        .next()
+        .unwrap_or("")
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| Range::parse(l).map_err(|e| format!("line {}: {}", i + 1, e)))
+        .collect::<Result<_, _>>()?;
 
     let values: Vec<u64> = parts
         .next()
-        .unwrap()
+        .unwrap_or("")
         .lines()
         .filter(|l| !l.is_empty())
-        .map(|l| l.parse().unwrap())
-        .collect();
+        .map(parse_id)
+        .collect::<Result<_, _>>()?;
 
-    (ranges, values)
+    Ok((ranges, values))
 }
 
 // Part 2 logic
@@ -52,32 +94,20 @@ fn merge_ranges(ranges: &[Range]) -> Vec<Range> {
     }
 
     // Sort by start value
-    let mut sorted: Vec<Range> = ranges
-        .iter()
-        .map(|r| Range {
-            start: r.start,
-            end: r.end,
-        })
-        .collect();
+    let mut sorted: Vec<Range> = ranges.to_vec();
     sorted.sort_by_key(|r| r.start);
 
     let mut merged: Vec<Range> = vec![];
-    let mut current = Range {
-        start: sorted[0].start,
-        end: sorted[0].end,
-    };
+    let mut current = sorted[0];
 
     for range in sorted.iter().skip(1) {
-        if range.start <= current.end {
+        if range.start <= current.end.saturating_add(1) {
             // Overlapping or adjacent - Extend
             current.end = current.end.max(range.end);
         } else {
             // GAp - push current and start new
             merged.push(current);
-            current = Range {
-                start: range.start,
-                end: range.end,
-            };
+            current = *range;
         }
     }
 
@@ -86,30 +116,511 @@ fn merge_ranges(ranges: &[Range]) -> Vec<Range> {
     merged
 }
 
-fn count_integers(ranges: &[Range]) -> u64 {
-    ranges.iter().map(|r| r.end - r.start + 1).sum()
+fn count_integers(ranges: &[Range]) -> u128 {
+    ranges.iter().map(Range::len).sum()
 }
 
-fn is_valid(value: u64, ranges: &[Range]) -> bool {
-    ranges.iter().any(|r| r.contains(value))
+// Set of IDs kept as sorted, disjoint, non-adjacent ranges
+#[derive(Debug, Clone)]
+struct RangeSet {
+    ranges: Vec<Range>,
+}
+
+impl RangeSet {
+    fn from_ranges(ranges: &[Range]) -> RangeSet {
+        RangeSet {
+            ranges: merge_ranges(ranges),
+        }
+    }
+
+    fn ranges(&self) -> &[Range] {
+        &self.ranges
+    }
+
+    fn contains(&self, value: u64) -> bool {
+        find_slot(&self.ranges, value).is_some()
+    }
+
+    // Number of IDs in the set
+    fn count(&self) -> u128 {
+        count_integers(&self.ranges)
+    }
+
+    // Number of values that are members of the set
+    fn count_members(&self, values: &[u64]) -> usize {
+        values.iter().filter(|&&v| self.contains(v)).count()
+    }
+
+    fn union(&self, other: &RangeSet) -> RangeSet {
+        let mut all = self.ranges.clone();
+        all.extend_from_slice(&other.ranges);
+        RangeSet::from_ranges(&all)
+    }
+
+    fn intersection(&self, other: &RangeSet) -> RangeSet {
+        let (a, b) = (&self.ranges, &other.ranges);
+        let mut ranges = vec![];
+        let (mut i, mut j) = (0, 0);
+
+        // Walk both lists, advancing whichever range ends first
+        while i < a.len() && j < b.len() {
+            let start = a[i].start.max(b[j].start);
+            let end = a[i].end.min(b[j].end);
+            if start <= end {
+                ranges.push(Range { start, end });
+            }
+            if a[i].end < b[j].end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        RangeSet { ranges }
+    }
+
+    fn difference(&self, other: &RangeSet) -> RangeSet {
+        match self.bounds() {
+            Some(bounds) => self.intersection(&other.complement(bounds)),
+            None => RangeSet { ranges: vec![] },
+        }
+    }
+
+    // Everything inside bounds that is not in the set
+    fn complement(&self, bounds: Range) -> RangeSet {
+        let mut ranges = vec![];
+        // Next ID not yet covered, None once we've run past u64::MAX
+        let mut next = Some(bounds.start);
+
+        for range in &self.ranges {
+            if range.end < bounds.start {
+                continue;
+            }
+            if range.start > bounds.end {
+                break;
+            }
+            let Some(n) = next else { break };
+            if range.start > n {
+                ranges.push(Range {
+                    start: n,
+                    end: range.start - 1,
+                });
+            }
+            next = range.end.checked_add(1);
+        }
+
+        if let Some(n) = next
+            && n <= bounds.end
+        {
+            ranges.push(Range {
+                start: n,
+                end: bounds.end,
+            });
+        }
+        RangeSet { ranges }
+    }
+
+    // Smallest range covering the whole set
+    fn bounds(&self) -> Option<Range> {
+        Some(Range {
+            start: self.ranges.first()?.start,
+            end: self.ranges.last()?.end,
+        })
+    }
+
+    // Missing IDs between consecutive ranges
+    fn gaps(&self) -> Vec<Range> {
+        self.ranges
+            .windows(2)
+            .map(|pair| Range {
+                start: pair[0].end + 1,
+                end: pair[1].start - 1,
+            })
+            .collect()
+    }
+}
+
+// Lookup index over the merged ranges
+// Merged ranges are sorted and disjoint, so a value can be found with a binary search.
+// For "which original ranges matched" the originals are kept sorted by start with a
+// sparse table of the furthest-reaching one over every power-of-two run
+struct RangeIndex {
+    merged: RangeSet,
+    original: Vec<Range>,
+    // Indices into `original`, sorted by start
+    by_start: Vec<usize>,
+    // furthest[k][i]: position in by_start of the largest end in by_start[i..i + 2^k]
+    furthest: Vec<Vec<usize>>,
+}
+
+impl RangeIndex {
+    fn new(ranges: &[Range]) -> RangeIndex {
+        let mut by_start: Vec<usize> = (0..ranges.len()).collect();
+        by_start.sort_by_key(|&i| ranges[i].start);
+
+        let end_at = |pos: usize| ranges[by_start[pos]].end;
+        let mut furthest = vec![(0..by_start.len()).collect::<Vec<usize>>()];
+        let mut width = 1;
+        while 2 * width <= by_start.len() {
+            let prev = furthest.last().unwrap();
+            let level: Vec<usize> = (0..=by_start.len() - 2 * width)
+                .map(|i| {
+                    let (a, b) = (prev[i], prev[i + width]);
+                    if end_at(b) > end_at(a) { b } else { a }
+                })
+                .collect();
+            furthest.push(level);
+            width *= 2;
+        }
+
+        RangeIndex {
+            merged: RangeSet::from_ranges(ranges),
+            original: ranges.to_vec(),
+            by_start,
+            furthest,
+        }
+    }
+
+    fn contains(&self, value: u64) -> bool {
+        self.merged.contains(value)
+    }
+
+    // Position in by_start of the range reaching furthest among by_start[lo..hi]
+    fn furthest_in(&self, lo: usize, hi: usize) -> usize {
+        let level = (hi - lo).ilog2() as usize;
+        let (a, b) = (
+            self.furthest[level][lo],
+            self.furthest[level][hi - (1 << level)],
+        );
+        if self.original[self.by_start[b]].end > self.original[self.by_start[a]].end {
+            b
+        } else {
+            a
+        }
+    }
+
+    // Indices of the original (unmerged) ranges containing value, in no particular order.
+    // Everything starting at or before value is a candidate. The furthest-reaching
+    // candidate either matches, and both sides of it get searched, or nothing there
+    // does, so each lookup is O(log n + matches)
+    fn matching(&self, value: u64) -> Vec<usize> {
+        let candidates = self
+            .by_start
+            .partition_point(|&i| self.original[i].start <= value);
+        let mut found = vec![];
+        let mut spans = vec![(0, candidates)];
+        while let Some((lo, hi)) = spans.pop() {
+            if lo >= hi {
+                continue;
+            }
+            let pos = self.furthest_in(lo, hi);
+            let i = self.by_start[pos];
+            if self.original[i].end < value {
+                continue;
+            }
+            found.push(i);
+            spans.push((lo, pos));
+            spans.push((pos + 1, hi));
+        }
+        found
+    }
+}
+
+// Binary search for the sorted, disjoint range containing value
+fn find_slot(sorted: &[Range], value: u64) -> Option<usize> {
+    // First range starting after value, the one before it is the only candidate
+    let idx = sorted.partition_point(|r| r.start <= value);
+    if idx > 0 && sorted[idx - 1].contains(value) {
+        Some(idx - 1)
+    } else {
+        None
+    }
+}
+
+fn is_valid(value: u64, index: &RangeIndex) -> bool {
+    index.contains(value)
+}
+
+// Streaming mode: read the ranges section, then check values one line at a time
+// Returns the index with (values checked, valid values)
+fn count_valid_streaming<R: BufRead>(mut reader: R) -> Result<(RangeIndex, usize, usize), String> {
+    let mut line = String::new();
+    let mut ranges = vec![];
+    let mut line_no = 0;
+
+    // Ranges run up to the first blank line
+    while reader.read_line(&mut line).map_err(|e| e.to_string())? > 0 {
+        line_no += 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            break;
+        }
+        ranges.push(Range::parse(trimmed).map_err(|e| format!("line {}: {}", line_no, e))?);
+        line.clear();
+    }
+    let index = RangeIndex::new(&ranges);
+
+    let mut checked = 0;
+    let mut valid = 0;
+    line.clear();
+    while reader.read_line(&mut line).map_err(|e| e.to_string())? > 0 {
+        line_no += 1;
+        let trimmed = line.trim();
+        if !trimmed.is_empty() {
+            checked += 1;
+            let value = parse_id(trimmed).map_err(|e| format!("line {}: {}", line_no, e))?;
+            if is_valid(value, &index) {
+                valid += 1;
+            }
+        }
+        line.clear();
+    }
+
+    Ok((index, checked, valid))
+}
+
+fn fail(source: &str, error: &str) -> ! {
+    eprintln!("Invalid {}: {}", source, error);
+    std::process::exit(1);
+}
+
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    let pos = args.iter().position(|a| a == flag)?;
+    Some(
+        args.get(pos + 1)
+            .map(String::as_str)
+            .unwrap_or_else(|| panic!("{} needs a value", flag)),
+    )
+}
+
+// Compare against the ranges of a second input file
+fn compare(fresh: &RangeSet, values: &[u64], path: &str) {
+    let other_input = read_to_string(path).expect("Could not read comparison file");
+    let (other_ranges, _) = parse_input(&other_input).unwrap_or_else(|e| fail(path, &e));
+    let other = RangeSet::from_ranges(&other_ranges);
+
+    println!("\nComparison with {}\n", path);
+    println!("Fresh in both: {}", fresh.intersection(&other).count());
+    println!("Fresh in either: {}", fresh.union(&other).count());
+    println!("Fresh only here: {}", fresh.difference(&other).count());
+    println!("Fresh only there: {}", other.difference(fresh).count());
+    println!(
+        "Ingredients fresh here but not there: {}",
+        fresh.difference(&other).count_members(values)
+    );
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    // Streaming mode for ID lists too large to hold in memory, "-" reads stdin
+    if let Some(source) = arg_value(&args, "--stream") {
+        let (index, checked, valid) = if source == "-" {
+            count_valid_streaming(io::stdin().lock())
+        } else {
+            let file = File::open(source).expect("Could not open stream file");
+            count_valid_streaming(BufReader::new(file))
+        }
+        .unwrap_or_else(|e| fail(if source == "-" { "stdin" } else { source }, &e));
+        println!(
+            "Streamed {} values against {} ranges",
+            checked,
+            index.original.len()
+        );
+        println!("Valid values: {}", valid);
+        return;
+    }
+
     let input = read_input();
-    let (ranges, values) = parse_input(&input);
+    let (ranges, values) = parse_input(&input).unwrap_or_else(|e| fail("input", &e));
 
     println!("Loaded {} ranges and {} values", ranges.len(), values.len());
 
-    let valid_counts = values.iter().filter(|&&v| is_valid(v, &ranges)).count();
+    let index = RangeIndex::new(&ranges);
+    let valid_counts = values.iter().filter(|&&v| is_valid(v, &index)).count();
+
+    // Values that more than one of the original ranges agree on
+    let overlapping = values
+        .iter()
+        .filter(|&&v| index.matching(v).len() > 1)
+        .count();
 
     println!("Part 1 \n");
     println!("Valid values: {}", valid_counts);
+    println!("Covered by overlapping ranges: {}", overlapping);
 
     // Part 2
-    let merged = merge_ranges(&ranges);
-    let total_integers = count_integers(&merged);
+    let merged = RangeSet::from_ranges(&ranges);
+    let total_integers = merged.count();
 
     println!("\nPart 2\n");
-    println!("merged into {} ranges", merged.len());
+    println!("merged into {} ranges", merged.ranges().len());
     println!("Total valid products: {}", total_integers);
+
+    // Spoiled IDs hiding between the fresh ranges
+    if let Some(bounds) = merged.bounds() {
+        println!(
+            "Spoiled IDs between {} and {}: {}",
+            bounds.start,
+            bounds.end,
+            merged.complement(bounds).count()
+        );
+    }
+    let mut gaps = merged.gaps();
+    gaps.sort_by_key(|g| std::cmp::Reverse(g.len()));
+    println!("Largest gaps:");
+    for gap in gaps.iter().take(5) {
+        println!("  {}-{} ({} IDs)", gap.start, gap.end, gap.len());
+    }
+
+    // Optional comparison with another range list
+    if let Some(path) = arg_value(&args, "--compare") {
+        compare(&merged, &values, path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+3-5
+10-14
+16-20
+12-18
+
+1
+5
+8
+11
+17
+32
+";
+
+    #[test]
+    fn test_example() {
+        let (ranges, values) = parse_input(EXAMPLE).unwrap();
+        let index = RangeIndex::new(&ranges);
+
+        let valid = values.iter().filter(|&&v| is_valid(v, &index)).count();
+        assert_eq!(valid, 3);
+        assert_eq!(count_integers(&merge_ranges(&ranges)), 14);
+    }
+
+    #[test]
+    fn test_index_matches_linear_scan() {
+        let (ranges, _) = parse_input(EXAMPLE).unwrap();
+        let index = RangeIndex::new(&ranges);
+
+        for value in 0..25 {
+            let expected: Vec<usize> = (0..ranges.len())
+                .filter(|&i| ranges[i].contains(value))
+                .collect();
+            let mut found = index.matching(value);
+            found.sort();
+            assert_eq!(found, expected, "value {}", value);
+            assert_eq!(index.contains(value), !expected.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_index_wide_merged_range() {
+        // One wide range swallowing lots of small ones, plus a few that overlap it
+        let mut ranges = vec![Range {
+            start: 0,
+            end: 1000,
+        }];
+        for i in 0..200 {
+            ranges.push(Range {
+                start: i * 5,
+                end: i * 5 + 2,
+            });
+        }
+        ranges.push(Range {
+            start: 900,
+            end: 1200,
+        });
+        let index = RangeIndex::new(&ranges);
+
+        for value in [0, 3, 501, 997, 1000, 1100, 1201] {
+            let expected: Vec<usize> = (0..ranges.len())
+                .filter(|&i| ranges[i].contains(value))
+                .collect();
+            let mut found = index.matching(value);
+            found.sort();
+            assert_eq!(found, expected, "value {}", value);
+        }
+    }
+
+    #[test]
+    fn test_streaming_matches_parse() {
+        let (index, checked, valid) = count_valid_streaming(EXAMPLE.as_bytes()).unwrap();
+        assert_eq!(index.original.len(), 4);
+        assert_eq!(checked, 6);
+        assert_eq!(valid, 3);
+    }
+
+    fn set(ranges: &[(u64, u64)]) -> RangeSet {
+        let ranges: Vec<Range> = ranges
+            .iter()
+            .map(|&(start, end)| Range { start, end })
+            .collect();
+        RangeSet::from_ranges(&ranges)
+    }
+
+    fn pairs(set: &RangeSet) -> Vec<(u64, u64)> {
+        set.ranges().iter().map(|r| (r.start, r.end)).collect()
+    }
+
+    #[test]
+    fn test_range_set_algebra() {
+        let a = set(&[(1, 10), (20, 30)]);
+        let b = set(&[(5, 22), (29, 40)]);
+
+        assert_eq!(pairs(&a.union(&b)), vec![(1, 40)]);
+        assert_eq!(
+            pairs(&a.intersection(&b)),
+            vec![(5, 10), (20, 22), (29, 30)]
+        );
+        assert_eq!(pairs(&a.difference(&b)), vec![(1, 4), (23, 28)]);
+        assert_eq!(pairs(&b.difference(&a)), vec![(11, 19), (31, 40)]);
+        assert_eq!(
+            pairs(&a.complement(Range { start: 0, end: 35 })),
+            vec![(0, 0), (11, 19), (31, 35)]
+        );
+        assert_eq!(pairs(&RangeSet { ranges: a.gaps() }), vec![(11, 19)]);
+        assert_eq!(a.count(), 21);
+        assert_eq!(a.count_members(&[0, 1, 15, 30, 31]), 2);
+    }
+
+    #[test]
+    fn test_complement_at_u64_max() {
+        let a = set(&[(10, u64::MAX)]);
+        let full = Range {
+            start: 0,
+            end: u64::MAX,
+        };
+        assert_eq!(pairs(&a.complement(full)), vec![(0, 9)]);
+        assert_eq!(pairs(&set(&[(3, 4), (5, 6)])), vec![(3, 6)]);
+    }
+
+    #[test]
+    fn test_count_full_range() {
+        let full = set(&[(0, u64::MAX)]);
+        assert_eq!(full.count(), u64::MAX as u128 + 1);
+    }
+
+    #[test]
+    fn test_parse_range_notations() {
+        let inclusive = Range::parse("3-5").unwrap();
+        assert_eq!((inclusive.start, inclusive.end), (3, 5));
+        let half_open = Range::parse("3..6").unwrap();
+        assert_eq!((half_open.start, half_open.end), (3, 5));
+        let explicit = Range::parse("3..=5").unwrap();
+        assert_eq!((explicit.start, explicit.end), (3, 5));
+
+        assert!(Range::parse("5-3").is_err());
+        assert!(Range::parse("4..4").is_err());
+        assert!(Range::parse("12").is_err());
+        assert!(Range::parse("a-3").is_err());
+    }
 }
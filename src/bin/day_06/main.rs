@@ -1,4 +1,5 @@
 use std::fs::read_to_string;
+use std::io::IsTerminal;
 
 // Helper functions
 fn read_input() -> String {
@@ -9,12 +10,153 @@ fn read_input() -> String {
         .expect("Could not find input.txt")
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Min,
+    Max,
+    Concat,
+    Gcd,
+    Lcm,
+}
+
+impl Operator {
+    fn from_char(c: char) -> Option<Operator> {
+        match c {
+            '+' => Some(Operator::Add),
+            '-' => Some(Operator::Sub),
+            '*' => Some(Operator::Mul),
+            '<' => Some(Operator::Min),
+            '>' => Some(Operator::Max),
+            '|' => Some(Operator::Concat),
+            'g' => Some(Operator::Gcd),
+            'l' => Some(Operator::Lcm),
+            _ => None,
+        }
+    }
+
+    fn symbol(self) -> char {
+        match self {
+            Operator::Add => '+',
+            Operator::Sub => '-',
+            Operator::Mul => '*',
+            Operator::Min => '<',
+            Operator::Max => '>',
+            Operator::Concat => '|',
+            Operator::Gcd => 'g',
+            Operator::Lcm => 'l',
+        }
+    }
+
+    // Combine two operands, None on overflow
+    fn apply(self, a: i128, b: i128) -> Option<i128> {
+        match self {
+            Operator::Add => a.checked_add(b),
+            Operator::Sub => a.checked_sub(b),
+            Operator::Mul => a.checked_mul(b),
+            Operator::Min => Some(a.min(b)),
+            Operator::Max => Some(a.max(b)),
+            // Digits of b appended to a, only defined for non-negative b
+            Operator::Concat => {
+                if b < 0 {
+                    return None;
+                }
+                let shift = 10i128.checked_pow(b.to_string().len() as u32)?;
+                a.checked_mul(shift)?.checked_add(b)
+            }
+            Operator::Gcd => gcd(a, b),
+            Operator::Lcm => {
+                if a == 0 || b == 0 {
+                    return Some(0);
+                }
+                (a / gcd(a, b)?).checked_mul(b)?.checked_abs()
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+fn gcd(a: i128, b: i128) -> Option<i128> {
+    let (mut a, mut b) = (a.checked_abs()?, b.checked_abs()?);
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    Some(a)
+}
+
+// A line made up of only operators and spaces
+fn is_operator_line(line: &str) -> bool {
+    !line.trim().is_empty()
+        && line
+            .chars()
+            .all(|c| c == ' ' || Operator::from_char(c).is_some())
+}
+
+const TAB_WIDTH: usize = 8;
+
+// Columns are display positions, so expand tabs and refuse anything that isn't ASCII
+// After this every byte is exactly one column wide
+fn normalize_worksheet(input: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(input.len());
+    for (row, line) in input.lines().enumerate() {
+        let mut width = 0;
+        for (col, c) in line.chars().enumerate() {
+            match c {
+                '\t' => {
+                    let stop = (width / TAB_WIDTH + 1) * TAB_WIDTH;
+                    out.push_str(&" ".repeat(stop - width));
+                    width = stop;
+                }
+                c if c.is_ascii() => {
+                    out.push(c);
+                    width += 1;
+                }
+                c => {
+                    return Err(format!(
+                        "line {}, character {}: non-ASCII '{}' (U+{:04X})",
+                        row + 1,
+                        col + 1,
+                        c,
+                        c as u32
+                    ));
+                }
+            }
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+// Part of a line in columns start..end, clipped to the line
+fn column_slice(line: &str, start: usize, end: usize) -> &str {
+    let len = line.len();
+    &line[start.min(len)..end.min(len)]
+}
+
 fn parse_input(input: &str) -> (Vec<&str>, &str) {
     let lines: Vec<&str> = input.lines().collect();
-    // First 4 lines are numbers, 5th line is operators
-    let number_lines = lines[0..4].to_vec();
-    let operator_line = lines[4];
-    (number_lines, operator_line)
+
+    // Operator row is the last operator-only line, falling back to the last non-empty line
+    let operator_idx = lines
+        .iter()
+        .rposition(|l| is_operator_line(l))
+        .or_else(|| lines.iter().rposition(|l| !l.trim().is_empty()))
+        .expect("Worksheet is empty");
+
+    // Every non-empty line above it holds operands
+    let number_lines = lines[..operator_idx]
+        .iter()
+        .copied()
+        .filter(|l| !l.trim().is_empty())
+        .collect();
+    (number_lines, lines[operator_idx])
 }
 
 fn find_column_ranges(lines: &[&str]) -> Vec<(usize, usize)> {
@@ -52,98 +194,500 @@ fn find_column_ranges(lines: &[&str]) -> Vec<(usize, usize)> {
     ranges
 }
 
-fn extract_column_values(lines: &[&str], start: usize, end: usize) -> Vec<i64> {
-    lines
+fn extract_operator(
+    line: &str,
+    start: usize,
+    end: usize,
+    column: usize,
+) -> Result<Operator, String> {
+    let slice = column_slice(line, start, end);
+    let mut symbols = slice.chars().filter(|&c| c != ' ');
+
+    let symbol = symbols
+        .next()
+        .ok_or_else(|| format!("column {}: missing operator", column))?;
+    if let Some(extra) = symbols.next() {
+        return Err(format!(
+            "column {}: more than one operator ('{}' and '{}')",
+            column, symbol, extra
+        ));
+    }
+    Operator::from_char(symbol)
+        .ok_or_else(|| format!("column {}: unknown operator '{}'", column, symbol))
+}
+
+fn apply_operation(values: &[i128], op: Operator) -> Option<i128> {
+    if values.is_empty() {
+        return Some(0);
+    }
+    values
         .iter()
-        .filter_map(|line| {
-            let slice = line.get(start..end).unwrap_or("");
-            let trimmed = slice.trim();
-            if trimmed.is_empty() {
-                None
-            } else {
-                trimmed.parse::<i64>().ok()
-            }
-        })
-        .collect()
+        .skip(1)
+        .try_fold(values[0], |acc, &val| op.apply(acc, val))
 }
 
-fn extract_operator(line: &str, start: usize, end: usize) -> char {
-    let slice = line.get(start..end).unwrap_or(" ");
-    slice.chars().find(|&c| c != ' ').unwrap_or('+')
+// Evaluate one problem, errors carry the column index
+fn evaluate_column(
+    values: &[Option<i128>],
+    operator_line: &str,
+    (start, end): (usize, usize),
+    column: usize,
+) -> Result<(Operator, i128), String> {
+    let op = extract_operator(operator_line, start, end, column)?;
+    if let Some(operand) = values.iter().position(Option::is_none) {
+        return Err(format!(
+            "column {}: operand {} overflows i128",
+            column, operand
+        ));
+    }
+    let values: Vec<i128> = values.iter().flatten().copied().collect();
+    let result = apply_operation(&values, op)
+        .ok_or_else(|| format!("column {}: {:?} {} overflows i128", column, values, op))?;
+    Ok((op, result))
 }
 
-fn apply_operation(values: &[i64], op: char) -> i64 {
-    if values.is_empty() {
-        return 0;
+fn fail(error: &str) -> ! {
+    eprintln!("Error: {}", error);
+    std::process::exit(1);
+}
+
+// How the numbers of a problem are read off the worksheet
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ReadingOrder {
+    // Each row is one number (Part 1)
+    Rows,
+    // Columns right to left, digits top-down (Part 2)
+    RightToLeft,
+    // Columns left to right, digits top-down
+    LeftToRight,
+    // Columns right to left, digits bottom-up
+    BottomUp,
+    // Down-right diagonals, starting from the bottom-left corner
+    Diagonal,
+}
+
+const READING_ORDERS: [ReadingOrder; 5] = [
+    ReadingOrder::Rows,
+    ReadingOrder::RightToLeft,
+    ReadingOrder::LeftToRight,
+    ReadingOrder::BottomUp,
+    ReadingOrder::Diagonal,
+];
+
+impl ReadingOrder {
+    fn name(self) -> &'static str {
+        match self {
+            ReadingOrder::Rows => "rows",
+            ReadingOrder::RightToLeft => "rtl",
+            ReadingOrder::LeftToRight => "ltr",
+            ReadingOrder::BottomUp => "bottom-up",
+            ReadingOrder::Diagonal => "diagonal",
+        }
+    }
+
+    fn parse(name: &str) -> Option<ReadingOrder> {
+        READING_ORDERS.into_iter().find(|o| o.name() == name)
+    }
+
+    // (row, position) cells making up each number, in reading order
+    fn cell_runs(self, rows: usize, width: usize) -> Vec<Vec<(usize, usize)>> {
+        if rows == 0 {
+            return vec![];
+        }
+        match self {
+            ReadingOrder::Rows => (0..rows)
+                .map(|r| (0..width).map(|p| (r, p)).collect())
+                .collect(),
+            ReadingOrder::RightToLeft => (0..width)
+                .rev()
+                .map(|p| (0..rows).map(|r| (r, p)).collect())
+                .collect(),
+            ReadingOrder::LeftToRight => (0..width)
+                .map(|p| (0..rows).map(|r| (r, p)).collect())
+                .collect(),
+            ReadingOrder::BottomUp => (0..width)
+                .rev()
+                .map(|p| (0..rows).rev().map(|r| (r, p)).collect())
+                .collect(),
+            // Diagonal k holds the cells where position - row == k - (rows - 1)
+            ReadingOrder::Diagonal => (0..rows + width - 1)
+                .map(|k| {
+                    (0..rows)
+                        .filter_map(|r| {
+                            let p = (k + r).checked_sub(rows - 1)?;
+                            (p < width).then_some((r, p))
+                        })
+                        .collect()
+                })
+                .collect(),
+        }
     }
-    values.iter().skip(1).fold(values[0], |acc, &val| match op {
-        '+' => acc + val,
-        '*' => acc * val,
-        _ => panic!("Unknown operator: {}", op),
-    })
 }
 
-// Part 2, extracting numbers right to left, going down each column
-fn extract_column_values_rtl(lines: &[&str], start: usize, end: usize) -> Vec<i64> {
-    let slices: Vec<&str> = lines
+// Read the numbers of the problem in columns start..end
+// One value per digit run, None when the digits don't fit in an i128
+fn extract_values(
+    lines: &[&str],
+    start: usize,
+    end: usize,
+    order: ReadingOrder,
+) -> Vec<Option<i128>> {
+    // Rows with nothing in this problem don't take part
+    let slices: Vec<&[u8]> = lines
         .iter()
-        .filter_map(|line| {
-            let slice = line.get(start..end).unwrap_or("");
-            if slice.trim().is_empty() {
-                None
-            } else {
-                Some(slice)
-            }
-        })
+        .map(|line| column_slice(line, start, end))
+        .filter(|slice| !slice.trim().is_empty())
+        .map(str::as_bytes)
         .collect();
 
-    let col_width = end - start;
-    let mut values = Vec::new();
+    order
+        .cell_runs(slices.len(), end - start)
+        .iter()
+        .filter_map(|run| {
+            let digits: String = run
+                .iter()
+                .filter_map(|&(r, p)| slices[r].get(p))
+                .filter(|c| c.is_ascii_digit())
+                .map(|&c| c as char)
+                .collect();
+            (!digits.is_empty()).then(|| digits.parse::<i128>().ok())
+        })
+        .collect()
+}
 
-    // Iterate positions from right to left within the columns
-    for pos in (0..col_width).rev() {
-        let mut digits = String::new();
-        for slice in &slices {
-            if let Some(c) = slice.chars().nth(pos) {
-                if c.is_ascii_digit() {
-                    digits.push(c);
-                }
+// Solve every problem with one reading order, returns each problem's result
+fn run_pass(
+    title: &str,
+    order: ReadingOrder,
+    number_lines: &[&str],
+    operator_line: &str,
+    column_ranges: &[(usize, usize)],
+) -> Vec<i128> {
+    let mut results = Vec::with_capacity(column_ranges.len());
+    let mut total: i128 = 0;
+    println!("=== {} ({}) ===", title, order.name());
+    for (i, &range) in column_ranges.iter().enumerate() {
+        let values = extract_values(number_lines, range.0, range.1, order);
+        let (op, result) =
+            evaluate_column(&values, operator_line, range, i).unwrap_or_else(|e| fail(&e));
+        // Debug output
+        let values: Vec<i128> = values.into_iter().flatten().collect();
+        println!("Column {}: {:?} {} = {}", i, values, op, result);
+        results.push(result);
+        total = total
+            .checked_add(result)
+            .unwrap_or_else(|| fail(&format!("{} total overflows i128", title)));
+    }
+    println!("{} Total: {}\n", title, total);
+    results
+}
+
+const HIGHLIGHT: &str = "\x1b[1;33m";
+const RESET: &str = "\x1b[0m";
+
+// Wrap text in a highlight when enabled
+fn paint(text: &str, highlight: bool) -> String {
+    if highlight {
+        format!("{}{}{}", HIGHLIGHT, text, RESET)
+    } else {
+        text.to_string()
+    }
+}
+
+// Lay results out under the start of their problem
+// Results too wide to fit drop to the next lane down instead of overlapping
+fn result_lanes(
+    column_ranges: &[(usize, usize)],
+    results: &[i128],
+    differs: &[bool],
+    color: bool,
+) -> Vec<String> {
+    // (rendered text, visible width)
+    let mut lanes: Vec<(String, usize)> = vec![];
+
+    for (i, &(start, _)) in column_ranges.iter().enumerate() {
+        let text = results[i].to_string();
+        let lane = match lanes
+            .iter()
+            .position(|&(_, width)| width == 0 || width < start)
+        {
+            Some(lane) => lane,
+            None => {
+                lanes.push((String::new(), 0));
+                lanes.len() - 1
             }
+        };
+        let (line, width) = &mut lanes[lane];
+        line.push_str(&" ".repeat(start - *width));
+        line.push_str(&paint(&text, color && differs[i]));
+        *width = start + text.len();
+    }
+    lanes.into_iter().map(|(line, _)| line).collect()
+}
+
+// Redraw the worksheet with both parts' results underneath each problem
+// Problems where the two readings disagree are highlighted and marked with ^
+fn render_worksheet(
+    number_lines: &[&str],
+    operator_line: &str,
+    column_ranges: &[(usize, usize)],
+    p1: &[i128],
+    p2: &[i128],
+    color: bool,
+) -> String {
+    let differs: Vec<bool> = p1.iter().zip(p2).map(|(a, b)| a != b).collect();
+    let width = number_lines
+        .iter()
+        .chain(std::iter::once(&operator_line))
+        .map(|l| l.len())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+
+    for line in number_lines.iter().chain(std::iter::once(&operator_line)) {
+        let mut rendered = String::new();
+        let mut pos = 0;
+        for (i, &(start, end)) in column_ranges.iter().enumerate() {
+            rendered.push_str(column_slice(line, pos, start));
+            let cell = format!("{:<w$}", column_slice(line, start, end), w = end - start);
+            rendered.push_str(&paint(&cell, color && differs[i]));
+            pos = end;
         }
-        if !digits.is_empty() {
-            values.push(digits.parse::<i64>().unwrap());
+        rendered.push_str(column_slice(line, pos, line.len()));
+        out.push_str(&format!("   {}\n", rendered.trim_end()));
+    }
+    out.push_str(&format!("   {}\n", "-".repeat(width)));
+
+    for (label, results) in [("p1", p1), ("p2", p2)] {
+        for (i, lane) in result_lanes(column_ranges, results, &differs, color)
+            .iter()
+            .enumerate()
+        {
+            let label = if i == 0 { label } else { "" };
+            out.push_str(&format!("{:<2} {}\n", label, lane));
         }
     }
-    values
+
+    // Plain markers under disagreeing problems, for when color is off
+    let mut markers = " ".repeat(width);
+    for (i, &(start, end)) in column_ranges.iter().enumerate() {
+        if differs[i] {
+            markers.replace_range(start..end, &"^".repeat(end - start));
+        }
+    }
+    out.push_str(&format!("   {}\n", markers.trim_end()));
+    out
 }
 
 fn main() {
-    let input = read_input();
+    let input = normalize_worksheet(&read_input()).unwrap_or_else(|e| fail(&e));
     let (number_lines, operator_line) = parse_input(&input);
     let column_ranges = find_column_ranges(&number_lines);
 
-    // Part 1
-    let mut total_p1: i64 = 0;
-    println!("=== Part 1 ===");
-    for (i, &(start, end)) in column_ranges.iter().enumerate() {
-        let values = extract_column_values(&number_lines, start, end);
-        let op = extract_operator(operator_line, start, end);
-        let result = apply_operation(&values, op);
-        // Debug output
-        println!("Column {}: {:?} {} = {}", i, values, op, result);
-        total_p1 += result;
+    let mut passes = vec![
+        ("Part 1", ReadingOrder::Rows),
+        ("Part 2", ReadingOrder::RightToLeft),
+    ];
+
+    // Extra pass with a reading order picked on the command line
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(pos) = args.iter().position(|a| a == "--order") {
+        let name = args.get(pos + 1).map(String::as_str).unwrap_or("");
+        let order = ReadingOrder::parse(name).unwrap_or_else(|| {
+            let names: Vec<&str> = READING_ORDERS.iter().map(|o| o.name()).collect();
+            fail(&format!(
+                "unknown reading order '{}', expected one of: {}",
+                name,
+                names.join(", ")
+            ))
+        });
+        passes.push(("Custom", order));
     }
-    println!("Part 1 Total: {}\n", total_p1);
 
-    // Part 2
-    let mut total_p2: i64 = 0;
-    println!("=== Part 2 ===");
-    for (i, &(start, end)) in column_ranges.iter().enumerate() {
-        let values = extract_column_values_rtl(&number_lines, start, end);
-        let op = extract_operator(operator_line, start, end);
-        let result = apply_operation(&values, op);
-        println!("Column {}: {:?} {} = {}", i, values, op, result);
-        total_p2 += result;
+    let results: Vec<Vec<i128>> = passes
+        .iter()
+        .map(|&(title, order)| run_pass(title, order, &number_lines, operator_line, &column_ranges))
+        .collect();
+
+    // Worksheet with results lined up under each problem, for eyeballing alignment
+    if args.iter().any(|a| a == "--render") {
+        let color = std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal();
+        let disagreements = results[0]
+            .iter()
+            .zip(&results[1])
+            .filter(|(a, b)| a != b)
+            .count();
+        println!("=== Worksheet ===");
+        print!(
+            "{}",
+            render_worksheet(
+                &number_lines,
+                operator_line,
+                &column_ranges,
+                &results[0],
+                &results[1],
+                color
+            )
+        );
+        println!(
+            "{} of {} problems read differently in parts 1 and 2",
+            disagreements,
+            column_ranges.len()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "123 328  51 64 \n 45 64  387 23 \n  6 98  215 314\n*   +   *   +  \n";
+
+    fn total(input: &str, order: ReadingOrder) -> i128 {
+        let (number_lines, operator_line) = parse_input(input);
+        find_column_ranges(&number_lines)
+            .into_iter()
+            .enumerate()
+            .map(|(i, (start, end))| {
+                let values = extract_values(&number_lines, start, end, order);
+                evaluate_column(&values, operator_line, (start, end), i)
+                    .unwrap()
+                    .1
+            })
+            .sum()
+    }
+
+    fn totals(input: &str) -> (i128, i128) {
+        (
+            total(input, ReadingOrder::Rows),
+            total(input, ReadingOrder::RightToLeft),
+        )
+    }
+
+    #[test]
+    fn test_example() {
+        assert_eq!(totals(EXAMPLE), (4277556, 3263827));
+    }
+
+    #[test]
+    fn test_operand_overflow() {
+        let check = |input: &str, order| {
+            let (number_lines, operator_line) = parse_input(input);
+            let range = find_column_ranges(&number_lines)[0];
+            let values = extract_values(&number_lines, range.0, range.1, order);
+            evaluate_column(&values, operator_line, range, 0).unwrap_err()
+        };
+
+        // A 40-digit operand doesn't fit in an i128
+        let input = format!("{}\n1\n+\n", "9".repeat(40));
+        assert_eq!(
+            check(&input, ReadingOrder::Rows),
+            "column 0: operand 0 overflows i128"
+        );
+
+        // Nor does a 40-row column read top to bottom
+        let input = "9\n".repeat(40) + "+\n";
+        assert_eq!(
+            check(&input, ReadingOrder::RightToLeft),
+            "column 0: operand 0 overflows i128"
+        );
+    }
+
+    #[test]
+    fn test_any_number_of_rows() {
+        let (number_lines, operator_line) = parse_input("1 2\n3 4\n\n+ *\n\n");
+        assert_eq!(number_lines, vec!["1 2", "3 4"]);
+        assert_eq!(operator_line, "+ *");
+        assert_eq!(totals("12 3\n+  *\n"), (15, 6));
+    }
+
+    #[test]
+    fn test_tabs_and_short_lines() {
+        // Tab stops line the second problem up with column 8
+        let input = normalize_worksheet("1\t23\n45      6\n+\t*\n").unwrap();
+        assert_eq!(input, "1       23\n45      6\n+       *\n");
+        let (number_lines, operator_line) = parse_input(&input);
+        assert_eq!(find_column_ranges(&number_lines), vec![(0, 2), (8, 10)]);
+        // Second line stops short of the last column but still counts
+        assert_eq!(
+            extract_values(&number_lines, 8, 10, ReadingOrder::Rows),
+            vec![Some(23), Some(6)]
+        );
+        assert_eq!(extract_operator(operator_line, 8, 10, 1), Ok(Operator::Mul));
+    }
+
+    #[test]
+    fn test_rejects_non_ascii() {
+        let err = normalize_worksheet("12 3\n4\u{a0}5\n").unwrap_err();
+        assert!(err.contains("line 2, character 2"), "{}", err);
+    }
+
+    #[test]
+    fn test_render_worksheet() {
+        let (number_lines, operator_line) = parse_input("11 12\n11 34\n+  + \n");
+        let ranges = find_column_ranges(&number_lines);
+        let rendered = render_worksheet(
+            &number_lines,
+            operator_line,
+            &ranges,
+            &[22, 46],
+            &[22, 37],
+            false,
+        );
+        let expected = "   11 12\n   11 34\n   +  +\n   -----\np1 22 46\np2 22 37\n      ^^\n";
+        assert_eq!(rendered, expected);
+
+        // Wide results drop to a second lane rather than overlapping
+        let lanes = result_lanes(&ranges, &[123456, 7], &[false, false], false);
+        assert_eq!(lanes, vec!["123456".to_string(), "   7".to_string()]);
+    }
+
+    #[test]
+    fn test_reading_orders() {
+        let lines = ["12", "34"];
+        let read = |order| -> Vec<i128> {
+            extract_values(&lines, 0, 2, order)
+                .into_iter()
+                .flatten()
+                .collect()
+        };
+        assert_eq!(read(ReadingOrder::Rows), vec![12, 34]);
+        assert_eq!(read(ReadingOrder::RightToLeft), vec![24, 13]);
+        assert_eq!(read(ReadingOrder::LeftToRight), vec![13, 24]);
+        assert_eq!(read(ReadingOrder::BottomUp), vec![42, 31]);
+        assert_eq!(read(ReadingOrder::Diagonal), vec![3, 14, 2]);
+        assert_eq!(
+            ReadingOrder::parse("bottom-up"),
+            Some(ReadingOrder::BottomUp)
+        );
+        assert_eq!(ReadingOrder::parse("sideways"), None);
+    }
+
+    #[test]
+    fn test_operators() {
+        let apply =
+            |values: &[i128], c: char| apply_operation(values, Operator::from_char(c).unwrap());
+        assert_eq!(apply(&[10, 3, 2], '-'), Some(5));
+        assert_eq!(apply(&[4, 9, 2], '<'), Some(2));
+        assert_eq!(apply(&[4, 9, 2], '>'), Some(9));
+        assert_eq!(apply(&[12, 3, 45], '|'), Some(12345));
+        assert_eq!(apply(&[12, 18, 30], 'g'), Some(6));
+        assert_eq!(apply(&[4, 6, 10], 'l'), Some(60));
+        assert_eq!(apply(&[i128::MAX, 2], '*'), None);
+    }
+
+    #[test]
+    fn test_operator_errors() {
+        assert!(
+            extract_operator("  ", 0, 2, 3)
+                .unwrap_err()
+                .contains("column 3")
+        );
+        assert!(
+            extract_operator("?", 0, 1, 0)
+                .unwrap_err()
+                .contains("unknown")
+        );
+        assert_eq!(extract_operator(" * ", 0, 3, 0), Ok(Operator::Mul));
     }
-    println!("Part 2 Total: {}\n", total_p2);
 }
@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::BTreeMap;
 use std::fs::read_to_string;
 
 // Helper functions
@@ -10,134 +10,790 @@ fn read_input() -> String {
         .expect("Could not find input.txt")
 }
 
-fn parse_grid(input: &str) -> Vec<Vec<char>> {
-    input.lines().map(|line| line.chars().collect()).collect()
+// Manifold components:
+//   S      source, emits a beam downwards
+//   .      empty space
+//   ^      splitter, a downward beam continues from both neighbouring columns
+//   / \    mirrors, turn the beam 90 degrees
+//   #      absorber, the beam stops
+//   v < >  re-emitters, send the beam on in the arrow's direction
+//   |      beam already drawn by --render, read back as empty space
+// Anything else is an error rather than a silent absorber
+fn parse_grid(input: &str) -> Result<Vec<Vec<char>>, String> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(r, line)| {
+            line.chars()
+                .enumerate()
+                .map(|(c, ch)| match ch {
+                    '|' => Ok('.'),
+                    'S' | '.' | '^' | '/' | '\\' | '#' | 'v' | '<' | '>' => Ok(ch),
+                    _ => Err(format!(
+                        "row {}, column {}: unknown component '{}'",
+                        r + 1,
+                        c + 1,
+                        ch
+                    )),
+                })
+                .collect()
+        })
+        .collect()
 }
 
-fn find_start(grid: &[Vec<char>]) -> Option<usize> {
-    grid.first()?.iter().position(|&c| c == 'S')
+// Only the original components, so the dense row-by-row sweeps apply
+fn is_downward_only(grid: &[Vec<char>]) -> bool {
+    grid.iter().flatten().all(|&c| matches!(c, 'S' | '.' | '^'))
 }
 
-fn simulate(grid: &mut Vec<Vec<char>>) -> usize {
-    let mut split_count = 0;
+// (row, col) of every source, in reading order
+fn find_sources(grid: &[Vec<char>]) -> Vec<(usize, usize)> {
+    let sources: Vec<(usize, usize)> = grid
+        .iter()
+        .enumerate()
+        .flat_map(|(r, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|&(_, &c)| c == 'S')
+                .map(move |(c, _)| (r, c))
+        })
+        .collect();
+    assert!(!sources.is_empty(), "No start position found");
+    sources
+}
+
+// Source columns grouped by row, for the row-by-row sweeps
+fn sources_by_row(grid: &[Vec<char>]) -> Vec<Vec<usize>> {
+    let mut by_row = vec![vec![]; grid.len()];
+    for (r, c) in find_sources(grid) {
+        by_row[r].push(c);
+    }
+    by_row
+}
+
+// Fixed-size set of columns, one bit per column
+struct Bitset {
+    words: Vec<u64>,
+}
+
+impl Bitset {
+    fn new(len: usize) -> Bitset {
+        Bitset {
+            words: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    fn insert(&mut self, i: usize) {
+        self.words[i / 64] |= 1 << (i % 64);
+    }
 
-    let start_col = find_start(grid).expect("No start position found");
+    fn contains(&self, i: usize) -> bool {
+        self.words[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    fn clear(&mut self) {
+        self.words.fill(0);
+    }
 
-    // Use Hashet to handle duplicates
-    let mut active_beams: HashSet<usize> = HashSet::new();
-    active_beams.insert(start_col);
+    fn is_empty(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
 
-    let rows = grid.len();
+    // Set columns in increasing order
+    fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(w, &word)| {
+            let mut bits = word;
+            std::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+                let bit = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                Some(w * 64 + bit)
+            })
+        })
+    }
+}
+
+// What the beams did, kept apart from the grid itself
+struct Simulation {
+    // Cells a beam passed through, one bitset per row
+    beams: Vec<Bitset>,
+    // (row, col) of every splitter a beam hit
+    splits: Vec<(usize, usize)>,
+}
+
+// Fast path for grids that only use S, . and ^, where beams always move down
+fn simulate_downward(grid: &[Vec<char>]) -> Simulation {
+    let sources = sources_by_row(grid);
+    let last_source_row = sources.iter().rposition(|s| !s.is_empty()).unwrap_or(0);
     let cols = grid[0].len();
 
+    let mut beams: Vec<Bitset> = grid.iter().map(|_| Bitset::new(cols)).collect();
+    let mut splits = vec![];
+
+    // Two reusable rows of beams, a bitset handles duplicates
+    let mut active_beams = Bitset::new(cols);
+    let mut new_beams = Bitset::new(cols);
+    for &col in &sources[0] {
+        active_beams.insert(col);
+    }
+
     // Process row by row
-    for row in 1..rows {
-        let mut new_beams: HashSet<usize> = HashSet::new();
+    for (r, row) in grid.iter().enumerate().skip(1) {
+        new_beams.clear();
+        let lit = &mut beams[r];
 
-        for &col in &active_beams {
-            match grid[row][col] {
+        for col in active_beams.iter() {
+            match row[col] {
                 '.' => {
                     // Beam continues down
-                    grid[row][col] = '|';
+                    lit.insert(col);
+                    new_beams.insert(col);
+                }
+                'S' => {
+                    // Beams pass straight through other sources
                     new_beams.insert(col);
                 }
                 '^' => {
                     // Beam splits left and right (same row)
-                    split_count += 1;
+                    splits.push((r, col));
 
                     // Left beam
                     if col > 0 {
-                        if grid[row][col - 1] == '.' {
-                            grid[row][col - 1] = '|';
+                        if row[col - 1] == '.' {
+                            lit.insert(col - 1);
                         }
                         new_beams.insert(col - 1);
                     }
                     // Right beam
                     if col + 1 < cols {
-                        if grid[row][col + 1] == '.' {
-                            grid[row][col + 1] = '|';
+                        if row[col + 1] == '.' {
+                            lit.insert(col + 1);
                         }
                         new_beams.insert(col + 1);
                     }
                 }
-                '|' => {
-                    // Already a beam here, continue
-                    new_beams.insert(col);
-                }
                 _ => {}
             }
         }
-        active_beams = new_beams;
+        // Sources on this row start new beams going down
+        for &col in &sources[r] {
+            new_beams.insert(col);
+        }
+        std::mem::swap(&mut active_beams, &mut new_beams);
 
-        if active_beams.is_empty() {
+        if active_beams.is_empty() && r >= last_source_row {
             break;
         }
     }
-    split_count
+    Simulation { beams, splits }
 }
 
-fn count_paths(grid: &[Vec<char>]) -> usize {
-    let start_col = find_start(grid).expect("No start position found");
+// Grid with the beams drawn in as '|'
+fn render(grid: &[Vec<char>], sim: &Simulation) -> String {
+    let mut out = String::new();
+    for (row, lit) in grid.iter().zip(&sim.beams) {
+        for (col, &c) in row.iter().enumerate() {
+            out.push(if c == '.' && lit.contains(col) {
+                '|'
+            } else {
+                c
+            });
+        }
+        out.push('\n');
+    }
+    out
+}
 
-    let rows = grid.len();
+// Path counts are u128 and checked, a silent wraparound would be a wrong answer
+fn add_paths(slot: &mut u128, count: u128) {
+    *slot = slot.checked_add(count).expect("Path count overflowed u128");
+}
+
+// Timelines through every cell, and how many leave through each exit
+struct PathCounts {
+    cells: Vec<Vec<u128>>,
+    exits: BTreeMap<Exit, u128>,
+    total: u128,
+}
+
+impl PathCounts {
+    fn new(cells: Vec<Vec<u128>>, exits: BTreeMap<Exit, u128>) -> PathCounts {
+        let total = sum_paths(exits.values().copied());
+        PathCounts {
+            cells,
+            exits,
+            total,
+        }
+    }
+}
+
+fn sum_paths(counts: impl IntoIterator<Item = u128>) -> u128 {
+    counts
+        .into_iter()
+        .try_fold(0u128, |acc, c| acc.checked_add(c))
+        .expect("Path count overflowed u128")
+}
+
+fn count_paths_downward(grid: &[Vec<char>]) -> PathCounts {
+    let sources = sources_by_row(grid);
+    let last_source_row = sources.iter().rposition(|s| !s.is_empty()).unwrap_or(0);
     let cols = grid[0].len();
 
-    // Each path is represented by its current column position
-    // We track how many paths are at each column
-    // Using a Vec of (column, path_count) or just count paths at each position
+    let mut cells = vec![vec![0u128; cols]; grid.len()];
+    let mut exits: BTreeMap<Exit, u128> = BTreeMap::new();
 
-    let mut path_counts: HashMap<usize, usize> = HashMap::new();
-    path_counts.insert(start_col, 1);
+    // Number of paths currently at each column, swapped between rows
+    let mut path_counts = vec![0u128; cols];
+    let mut new_counts = vec![0u128; cols];
+    for &col in &sources[0] {
+        path_counts[col] = 1;
+        cells[0][col] = 1;
+    }
 
-    for row in 1..rows {
-        let mut new_counts: HashMap<usize, usize> = HashMap::new();
+    for (r, row) in grid.iter().enumerate().skip(1) {
+        new_counts.fill(0);
+        let through = &mut cells[r];
 
-        for (&col, &count) in &path_counts {
-            match grid[row][col] {
-                '.' | '|' => {
+        for (col, &count) in path_counts.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            add_paths(&mut through[col], count);
+            match row[col] {
+                '.' | 'S' => {
                     // Path continues straigth down
-                    *new_counts.entry(col).or_insert(0) += count;
+                    add_paths(&mut new_counts[col], count);
                 }
                 '^' => {
-                    // Each path splits in two
+                    // Each path splits in two, falling off the side counts as an exit
                     if col > 0 {
-                        *new_counts.entry(col - 1).or_insert(0) += count;
+                        add_paths(&mut new_counts[col - 1], count);
+                        add_paths(&mut through[col - 1], count);
+                    } else {
+                        add_paths(exits.entry(Exit::Left(r)).or_insert(0), count);
                     }
                     if col + 1 < cols {
-                        *new_counts.entry(col + 1).or_insert(0) += count;
+                        add_paths(&mut new_counts[col + 1], count);
+                        add_paths(&mut through[col + 1], count);
+                    } else {
+                        add_paths(exits.entry(Exit::Right(r)).or_insert(0), count);
                     }
                 }
                 _ => {}
             }
         }
 
-        path_counts = new_counts;
+        // Sources on this row start one new timeline each
+        for &col in &sources[r] {
+            add_paths(&mut new_counts[col], 1);
+            add_paths(&mut through[col], 1);
+        }
+
+        std::mem::swap(&mut path_counts, &mut new_counts);
 
-        if path_counts.is_empty() {
+        if path_counts.iter().all(|&c| c == 0) && r >= last_source_row {
             break;
         }
     }
 
-    // Total paths = sum of all path counts at the bottom
-    path_counts.values().sum()
+    // Whatever is left reaches the bottom
+    for (col, &count) in path_counts.iter().enumerate() {
+        if count > 0 {
+            add_paths(exits.entry(Exit::Bottom(col)).or_insert(0), count);
+        }
+    }
+    PathCounts::new(cells, exits)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dir {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Dir {
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+// Where a beam leaves the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Exit {
+    Top(usize),
+    Bottom(usize),
+    Left(usize),
+    Right(usize),
+}
+
+impl std::fmt::Display for Exit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Exit::Top(col) => write!(f, "top, col {}", col),
+            Exit::Bottom(col) => write!(f, "bottom, col {}", col),
+            Exit::Left(row) => write!(f, "left side, row {}", row),
+            Exit::Right(row) => write!(f, "right side, row {}", row),
+        }
+    }
+}
+
+// A beam inside a cell, moving in dir
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Beam {
+    row: usize,
+    col: usize,
+    dir: Dir,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Next {
+    Beam(Beam),
+    Exit(Exit),
+}
+
+// Move one cell in dir, or leave the grid
+fn step(grid: &[Vec<char>], row: usize, col: usize, dir: Dir) -> Next {
+    let last_row = grid.len() - 1;
+    let last_col = grid[row].len() - 1;
+    let (row, col) = match dir {
+        Dir::Up if row == 0 => return Next::Exit(Exit::Top(col)),
+        Dir::Down if row == last_row => return Next::Exit(Exit::Bottom(col)),
+        Dir::Left if col == 0 => return Next::Exit(Exit::Left(row)),
+        Dir::Right if col == last_col => return Next::Exit(Exit::Right(row)),
+        Dir::Up => (row - 1, col),
+        Dir::Down => (row + 1, col),
+        Dir::Left => (row, col - 1),
+        Dir::Right => (row, col + 1),
+    };
+    Next::Beam(Beam { row, col, dir })
+}
+
+// Where a beam goes after entering its cell
+fn advance(grid: &[Vec<char>], beam: Beam) -> Vec<Next> {
+    let Beam { row, col, dir } = beam;
+    let turn = |dir| vec![step(grid, row, col, dir)];
+
+    match grid[row][col] {
+        '.' | 'S' => turn(dir),
+        // Splitters only act on downward beams, like the original puzzle
+        '^' if dir == Dir::Down => {
+            let left = if col == 0 {
+                Next::Exit(Exit::Left(row))
+            } else {
+                step(grid, row, col - 1, Dir::Down)
+            };
+            let right = if col + 1 == grid[row].len() {
+                Next::Exit(Exit::Right(row))
+            } else {
+                step(grid, row, col + 1, Dir::Down)
+            };
+            vec![left, right]
+        }
+        '^' => turn(dir),
+        '/' => turn(match dir {
+            Dir::Down => Dir::Left,
+            Dir::Up => Dir::Right,
+            Dir::Right => Dir::Up,
+            Dir::Left => Dir::Down,
+        }),
+        '\\' => turn(match dir {
+            Dir::Down => Dir::Right,
+            Dir::Up => Dir::Left,
+            Dir::Right => Dir::Down,
+            Dir::Left => Dir::Up,
+        }),
+        'v' => turn(Dir::Down),
+        '<' => turn(Dir::Left),
+        '>' => turn(Dir::Right),
+        // Absorbers stop the beam
+        '#' => vec![],
+        other => unreachable!("parse_grid lets no '{}' through", other),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Link {
+    State(usize),
+    Exit(Exit),
+}
+
+// Every beam state reachable from the sources, with where each one leads
+struct BeamGraph {
+    states: Vec<Beam>,
+    links: Vec<Vec<Link>>,
+}
+
+fn explore(grid: &[Vec<char>], sources: &[Beam]) -> BeamGraph {
+    let cols = grid[0].len();
+    let key = |b: Beam| (b.row * cols + b.col) * 4 + b.dir.index();
+
+    // State index by (cell, direction), usize::MAX until seen
+    let mut ids = vec![usize::MAX; grid.len() * cols * 4];
+    let mut states: Vec<Beam> = vec![];
+    let mut links: Vec<Vec<Link>> = vec![];
+
+    for &source in sources {
+        if ids[key(source)] == usize::MAX {
+            ids[key(source)] = states.len();
+            states.push(source);
+        }
+    }
+
+    // Breadth-first, a state that was already seen is never expanded twice
+    let mut i = 0;
+    while i < states.len() {
+        let mut out = vec![];
+        for next in advance(grid, states[i]) {
+            match next {
+                Next::Exit(exit) => out.push(Link::Exit(exit)),
+                Next::Beam(beam) => {
+                    if ids[key(beam)] == usize::MAX {
+                        ids[key(beam)] = states.len();
+                        states.push(beam);
+                    }
+                    out.push(Link::State(ids[key(beam)]));
+                }
+            }
+        }
+        links.push(out);
+        i += 1;
+    }
+    BeamGraph { states, links }
+}
+
+// Every source emits one beam downwards
+fn sources(grid: &[Vec<char>]) -> Vec<Beam> {
+    find_sources(grid)
+        .into_iter()
+        .map(|(row, col)| Beam {
+            row,
+            col,
+            dir: Dir::Down,
+        })
+        .collect()
+}
+
+// General simulation, beams can turn, stop and loop
+fn propagate(grid: &[Vec<char>]) -> Simulation {
+    let graph = explore(grid, &sources(grid));
+    let cols = grid[0].len();
+    let mut beams: Vec<Bitset> = grid.iter().map(|_| Bitset::new(cols)).collect();
+    let mut splits = vec![];
+
+    for beam in &graph.states {
+        beams[beam.row].insert(beam.col);
+        if grid[beam.row][beam.col] == '^' && beam.dir == Dir::Down {
+            splits.push((beam.row, beam.col));
+            // The split beams pass through the neighbouring cells
+            if beam.col > 0 {
+                beams[beam.row].insert(beam.col - 1);
+            }
+            if beam.col + 1 < cols {
+                beams[beam.row].insert(beam.col + 1);
+            }
+        }
+    }
+    splits.sort();
+    Simulation { beams, splits }
+}
+
+// Timelines in a general grid, counted in topological order over the beam states
+// A loop means infinitely many timelines, so that is an error
+fn count_paths_general(grid: &[Vec<char>]) -> Result<PathCounts, String> {
+    let sources = sources(grid);
+    let graph = explore(grid, &sources);
+    let n = graph.states.len();
+    let cols = grid[0].len();
+
+    let mut incoming = vec![0usize; n];
+    for link in graph.links.iter().flatten() {
+        if let Link::State(j) = *link {
+            incoming[j] += 1;
+        }
+    }
+
+    // explore numbers the sources first, each starts one timeline
+    let mut counts = vec![0u128; n];
+    for count in counts.iter_mut().take(sources.len()) {
+        *count = 1;
+    }
+    let mut ready: Vec<usize> = (0..n).filter(|&i| incoming[i] == 0).collect();
+
+    let mut cells = vec![vec![0u128; cols]; grid.len()];
+    // Timelines leaving through each exit
+    let mut exits: BTreeMap<Exit, u128> = BTreeMap::new();
+    let mut done = 0;
+    while let Some(i) = ready.pop() {
+        done += 1;
+        let count = counts[i];
+        let Beam { row, col, dir } = graph.states[i];
+        add_paths(&mut cells[row][col], count);
+        // Split beams pass through the neighbouring cells
+        if grid[row][col] == '^' && dir == Dir::Down {
+            for side in [col.wrapping_sub(1), col + 1] {
+                if side < cols {
+                    add_paths(&mut cells[row][side], count);
+                }
+            }
+        }
+
+        for link in &graph.links[i] {
+            match *link {
+                Link::Exit(exit) => add_paths(exits.entry(exit).or_insert(0), count),
+                Link::State(j) => {
+                    add_paths(&mut counts[j], count);
+                    incoming[j] -= 1;
+                    if incoming[j] == 0 {
+                        ready.push(j);
+                    }
+                }
+            }
+        }
+    }
+
+    if done < n {
+        // Anything left over sits on or behind a loop
+        let stuck = graph.states[(0..n).find(|&i| incoming[i] > 0).unwrap()];
+        return Err(format!(
+            "beams loop forever (stuck at row {}, col {})",
+            stuck.row, stuck.col
+        ));
+    }
+    Ok(PathCounts::new(cells, exits))
+}
+
+fn simulate(grid: &[Vec<char>]) -> Simulation {
+    if is_downward_only(grid) {
+        simulate_downward(grid)
+    } else {
+        propagate(grid)
+    }
+}
+
+fn count_paths(grid: &[Vec<char>]) -> Result<PathCounts, String> {
+    if is_downward_only(grid) {
+        Ok(count_paths_downward(grid))
+    } else {
+        count_paths_general(grid)
+    }
+}
+
+// Every splitter in the grid and whether any beam reached it
+fn splitter_hits(grid: &[Vec<char>], sim: &Simulation) -> Vec<((usize, usize), bool)> {
+    let mut hit = vec![vec![false; grid[0].len()]; grid.len()];
+    for &(row, col) in &sim.splits {
+        hit[row][col] = true;
+    }
+    let mut splitters = vec![];
+    for (row, line) in grid.iter().enumerate() {
+        for (col, &c) in line.iter().enumerate() {
+            if c == '^' {
+                splitters.push(((row, col), hit[row][col]));
+            }
+        }
+    }
+    splitters
+}
+
+// Cool to hot 256-color backgrounds
+const HEAT_RAMP: [u8; 16] = [
+    17, 18, 19, 20, 21, 27, 33, 39, 45, 51, 50, 48, 46, 226, 208, 196,
+];
+
+// Grid drawn over ANSI backgrounds, colored by log(timelines) through each cell
+fn heatmap(grid: &[Vec<char>], counts: &PathCounts) -> String {
+    let max = counts.cells.iter().flatten().copied().max().unwrap_or(0);
+    let scale = ((max as f64) + 1.0).ln();
+    let mut out = String::new();
+
+    for (row, line) in grid.iter().zip(&counts.cells) {
+        for (&c, &count) in row.iter().zip(line) {
+            if count == 0 {
+                out.push(c);
+                continue;
+            }
+            let level = ((count as f64) + 1.0).ln() / scale;
+            let idx =
+                ((level * (HEAT_RAMP.len() - 1) as f64).round() as usize).min(HEAT_RAMP.len() - 1);
+            out.push_str(&format!("\x1b[48;5;{}m{}\x1b[0m", HEAT_RAMP[idx], c));
+        }
+        out.push('\n');
+    }
+    out
 }
 
 fn main() {
     let input = read_input();
-    let mut grid = parse_grid(&input);
+    let grid = parse_grid(&input).unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    });
 
-    let splits = simulate(&mut grid);
+    let sim = simulate(&grid);
 
     // Part 2
     let paths = count_paths(&grid);
 
     // Debug print final grid
-    println!("=== Final Grid ===");
-    for row in grid {
-        let line: String = row.iter().collect();
-        println!("{}", line);
+    if std::env::args().any(|a| a == "--render") {
+        println!("=== Final Grid ===");
+        print!("{}", render(&grid, &sim));
+    }
+
+    let splitters = splitter_hits(&grid, &sim);
+    let unused = splitters.iter().filter(|&&(_, hit)| !hit).count();
+
+    println!("Part 1 Total: {}", sim.splits.len());
+    println!(
+        "Splitters never reached: {} of {}\n",
+        unused,
+        splitters.len()
+    );
+
+    match paths {
+        Ok(paths) => {
+            println!("Part 2: {} unique paths", paths.total);
+
+            println!("Timelines per exit:");
+            for (exit, count) in &paths.exits {
+                println!("  {}: {}", exit, count);
+            }
+
+            // Splitters carrying the most timelines
+            let mut busiest: Vec<(usize, usize)> = sim.splits.clone();
+            busiest.sort_by_key(|&(r, c)| std::cmp::Reverse(paths.cells[r][c]));
+            println!("Busiest splitters:");
+            for &(r, c) in busiest.iter().take(5) {
+                println!("  row {}, col {}: {} timelines", r, c, paths.cells[r][c]);
+            }
+
+            if std::env::args().any(|a| a == "--heatmap") {
+                println!("=== Timeline Heatmap ===");
+                print!("{}", heatmap(&grid, &paths));
+            }
+        }
+        Err(e) => println!("Part 2: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+.......S.......
+...............
+.......^.......
+...............
+......^.^......
+...............
+.....^.^.^.....
+...............
+....^.^...^....
+...............
+...^.^...^.^...
+...............
+..^...^.....^..
+...............
+.^.^.^.^.^...^.
+...............
+";
+
+    #[test]
+    fn test_parse_grid() {
+        // Rendered output reads back the same as the original
+        let sim = simulate(&parse_grid(EXAMPLE).unwrap());
+        let rendered = render(&parse_grid(EXAMPLE).unwrap(), &sim);
+        assert!(rendered.contains('|'));
+        let reread = parse_grid(&rendered).unwrap();
+        assert_eq!(reread, parse_grid(EXAMPLE).unwrap());
+        assert_eq!(simulate(&reread).splits.len(), 21);
+
+        assert_eq!(
+            parse_grid("..S..\n..x..\n").unwrap_err(),
+            "row 2, column 3: unknown component 'x'"
+        );
+    }
+
+    #[test]
+    fn test_example() {
+        let grid = parse_grid(EXAMPLE).unwrap();
+        // Either order works, simulate leaves the grid alone
+        assert_eq!(count_paths(&grid).unwrap().total, 40);
+        let sim = simulate(&grid);
+        assert_eq!(sim.splits.len(), 21);
+        let paths = count_paths(&grid).unwrap();
+        assert_eq!(paths.total, 40);
+
+        // The general engine agrees with the dense sweeps
+        assert_eq!(propagate(&grid).splits, sim.splits);
+        let general = count_paths_general(&grid).unwrap();
+        assert_eq!(general.total, 40);
+        assert_eq!(general.cells, paths.cells);
+
+        // Timelines reaching the bottom row add up to the total
+        assert_eq!(paths.cells[15].iter().sum::<u128>(), 40);
+        assert_eq!(paths.cells[2][7], 1);
+        assert_eq!(paths.cells[4][7], 2);
+
+        let splitters = splitter_hits(&grid, &sim);
+        assert_eq!(splitters.len(), 22);
+        assert_eq!(splitters.iter().filter(|&&(_, hit)| !hit).count(), 1);
+
+        let rendered = render(&grid, &sim);
+        assert_eq!(rendered.lines().nth(2), Some("......|^|......"));
     }
 
-    println!("Part 1 Total: {}\n", splits);
-    println!("Part 2: {} unique paths", paths);
+    #[test]
+    fn test_mirrors_and_absorbers() {
+        let grid = parse_grid("..S..\n.....\n..^..\n.#.\\.\n.....\n").unwrap();
+        let sim = simulate(&grid);
+        assert_eq!(sim.splits, vec![(2, 2)]);
+        // Left half is absorbed, right half turns off the mirror and leaves sideways
+        assert_eq!(count_paths(&grid).unwrap().total, 1);
+        assert!(sim.beams[3].contains(4) && !sim.beams[3].contains(0));
+    }
+
+    #[test]
+    fn test_multiple_sources_and_side_exits() {
+        let grid = parse_grid("S....\n.....\n^....\n..S..\n.....\n").unwrap();
+        let sim = simulate(&grid);
+        assert_eq!(sim.splits, vec![(2, 0)]);
+
+        let expected = BTreeMap::from([
+            (Exit::Bottom(1), 1),
+            (Exit::Bottom(2), 1),
+            (Exit::Left(2), 1),
+        ]);
+        let paths = count_paths(&grid).unwrap();
+        assert_eq!(paths.exits, expected);
+        assert_eq!(paths.total, 3);
+
+        let general = count_paths_general(&grid).unwrap();
+        assert_eq!(general.exits, expected);
+        assert_eq!(general.cells, paths.cells);
+    }
+
+    #[test]
+    fn test_loop_detected() {
+        let grid = parse_grid(".S..\n.\\.v\n.>.<\n").unwrap();
+        let sim = simulate(&grid);
+        assert!(sim.beams[2].contains(1) && sim.beams[2].contains(3));
+        assert!(count_paths(&grid).is_err());
+    }
+
+    #[test]
+    fn test_bitset_iter() {
+        let mut set = Bitset::new(130);
+        for i in [0, 63, 64, 129] {
+            set.insert(i);
+        }
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![0, 63, 64, 129]);
+        set.clear();
+        assert!(set.is_empty());
+    }
 }
@@ -1,5 +1,9 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::fs::read_to_string;
 
+use aoc2025::union_find::UnionFind;
+
 // Helper functions
 fn read_input() -> String {
     // Try relative path first (for running from bin directory)
@@ -9,168 +13,1090 @@ fn read_input() -> String {
         .expect("Could not find input.txt")
 }
 
-#[derive(Debug, Clone, Copy)]
+// Junction box position, any number of dimensions (the puzzle uses 3)
+#[derive(Debug, Clone, PartialEq)]
 struct Point {
-    x: i64,
-    y: i64,
-    z: i64,
+    coords: Vec<i64>,
 }
 
-// For storing a pair of points and their distance
+impl Point {
+    fn dims(&self) -> usize {
+        self.coords.len()
+    }
+}
+
+// For storing a pair of points and their distance (in the metric's own units)
+#[derive(Debug, Clone, Copy, PartialEq)]
 struct Edge {
     i: usize,
     j: usize,
-    dist_sq: i64,
+    dist: i64,
 }
 
 fn parse_points(input: &str) -> Vec<Point> {
-    input
+    let points: Vec<Point> = input
         .lines()
         .filter(|line| !line.is_empty())
-        .map(|line| {
-            let parts: Vec<i64> = line.split(',').map(|s| s.trim().parse().unwrap()).collect();
-            Point {
-                x: parts[0],
-                y: parts[1],
-                z: parts[2],
-            }
+        .map(|line| Point {
+            coords: line.split(',').map(|s| s.trim().parse().unwrap()).collect(),
         })
-        .collect()
+        .collect();
+
+    // Every point needs the same number of coordinates
+    if let Some(first) = points.first() {
+        for (i, p) in points.iter().enumerate() {
+            if p.dims() != first.dims() {
+                panic!(
+                    "Point {} has {} coordinates, expected {}",
+                    i + 1,
+                    p.dims(),
+                    first.dims()
+                );
+            }
+        }
+    }
+    points
 }
 
-fn distance_squared(a: &Point, b: &Point) -> i64 {
-    let dx = a.x - b.x;
-    let dy = a.y - b.y;
-    let dz = a.z - b.z;
-    dx * dx + dy * dy + dz * dz
+// Wiring cost between two boxes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Metric {
+    // Stored squared so everything stays in integers
+    Euclidean,
+    Manhattan,
+    Chebyshev,
 }
 
-fn compute_all_edges(points: &[Point]) -> Vec<Edge> {
-    let mut edges = Vec::new();
-    for i in 0..points.len() {
-        for j in (i + 1)..points.len() {
-            edges.push(Edge {
-                i,
-                j,
-                dist_sq: distance_squared(&points[i], &points[j]),
-            });
+impl Metric {
+    fn parse(name: &str) -> Option<Metric> {
+        match name {
+            "euclidean" => Some(Metric::Euclidean),
+            "manhattan" => Some(Metric::Manhattan),
+            "chebyshev" => Some(Metric::Chebyshev),
+            _ => None,
+        }
+    }
+
+    fn distance(&self, a: &Point, b: &Point) -> i64 {
+        let diffs = a.coords.iter().zip(&b.coords).map(|(x, y)| x - y);
+        match self {
+            Metric::Euclidean => diffs.map(|d| d * d).sum(),
+            Metric::Manhattan => diffs.map(i64::abs).sum(),
+            Metric::Chebyshev => diffs.map(i64::abs).max().unwrap_or(0),
+        }
+    }
+
+    // Lowest possible distance to anything on the far side of a split `diff` away
+    fn axis_bound(&self, diff: i64) -> i64 {
+        match self {
+            Metric::Euclidean => diff * diff,
+            Metric::Manhattan | Metric::Chebyshev => diff.abs(),
+        }
+    }
+
+    // Real length of a stored distance
+    fn length(&self, dist: i64) -> f64 {
+        match self {
+            Metric::Euclidean => (dist as f64).sqrt(),
+            Metric::Manhattan | Metric::Chebyshev => dist as f64,
+        }
+    }
+
+    // Largest stored distance whose length is within the cutoff
+    fn cutoff(&self, length: f64) -> i64 {
+        match self {
+            Metric::Euclidean => (length * length).floor() as i64,
+            Metric::Manhattan | Metric::Chebyshev => length.floor() as i64,
         }
     }
-    // sort by distance
-    edges.sort_by_key(|e| e.dist_sq);
-    edges
 }
 
-// Use Union-Find to find connected components
-struct UnionFind {
-    parent: Vec<usize>,
-    size: Vec<usize>,
+// KD-tree stored implicitly: the median of order[lo..hi] sits at the midpoint,
+// split on axis depth % dims, with the two halves on either side
+struct KdTree<'a> {
+    points: &'a [Point],
+    metric: Metric,
+    dims: usize,
+    order: Vec<usize>,
 }
 
-impl UnionFind {
-    fn new(n: usize) -> Self {
-        UnionFind {
-            parent: (0..n).collect(),
-            size: vec![1; n],
+impl<'a> KdTree<'a> {
+    fn new(points: &'a [Point], metric: Metric) -> Self {
+        let dims = points.first().map_or(1, |p| p.dims().max(1));
+        let mut order: Vec<usize> = (0..points.len()).collect();
+        Self::build(points, dims, &mut order, 0);
+        KdTree {
+            points,
+            metric,
+            dims,
+            order,
         }
     }
 
-    // Find the root of the circuit containing x (with path compression)
-    fn find(&mut self, x: usize) -> usize {
-        if self.parent[x] != x {
-            self.parent[x] = self.find(self.parent[x]);
+    fn build(points: &[Point], dims: usize, order: &mut [usize], depth: usize) {
+        if order.len() <= 1 {
+            return;
         }
-        self.parent[x]
+        let mid = order.len() / 2;
+        let axis = depth % dims;
+        order.select_nth_unstable_by_key(mid, |&i| points[i].coords.get(axis).copied());
+        let (left, right) = order.split_at_mut(mid);
+        Self::build(points, dims, left, depth + 1);
+        Self::build(points, dims, &mut right[1..], depth + 1);
+    }
+
+    // The k nearest other points to points[query] that come after `after`,
+    // sorted by (distance, index)
+    fn nearest_after(
+        &self,
+        query: usize,
+        k: usize,
+        after: Option<(i64, usize)>,
+    ) -> Vec<(i64, usize)> {
+        let mut best = BinaryHeap::new();
+        let wanted = Wanted { query, k, after };
+        self.search(&wanted, 0, self.order.len(), 0, &mut best);
+        best.into_sorted_vec()
     }
-    // Unite two circuits, returns true if separate
-    fn union(&mut self, x: usize, y: usize) -> bool {
-        let root_x = self.find(x);
-        let root_y = self.find(y);
 
-        if root_x == root_y {
-            return false; // already in same circuit
+    fn search(
+        &self,
+        wanted: &Wanted,
+        lo: usize,
+        hi: usize,
+        depth: usize,
+        best: &mut BinaryHeap<(i64, usize)>,
+    ) {
+        if lo >= hi || wanted.k == 0 {
+            return;
         }
+        let mid = lo + (hi - lo) / 2;
+        let node = self.order[mid];
+        let q = &self.points[wanted.query];
 
-        // Union by size (attach smaller tree to larger)
-        if self.size[root_x] < self.size[root_y] {
-            self.parent[root_x] = root_y;
-            self.size[root_y] += self.size[root_x];
+        if node != wanted.query {
+            let candidate = (self.metric.distance(q, &self.points[node]), node);
+            if wanted.after.is_some_and(|after| candidate <= after) {
+                // Already handed out
+            } else if best.len() < wanted.k {
+                best.push(candidate);
+            } else if candidate < *best.peek().unwrap() {
+                best.pop();
+                best.push(candidate);
+            }
+        }
+
+        // Search the side of the split holding the query first
+        let diff = self.split_diff(q, node, depth);
+        let (near, far) = if diff < 0 {
+            ((lo, mid), (mid + 1, hi))
+        } else {
+            ((mid + 1, hi), (lo, mid))
+        };
+        self.search(wanted, near.0, near.1, depth + 1, best);
+
+        // Only cross the split if something over there could still make the cut
+        if best.len() < wanted.k || self.metric.axis_bound(diff) <= best.peek().unwrap().0 {
+            self.search(wanted, far.0, far.1, depth + 1, best);
+        }
+    }
+
+    // How far the query sits from the split through node, along that level's axis
+    fn split_diff(&self, q: &Point, node: usize, depth: usize) -> i64 {
+        let axis = depth % self.dims;
+        let coord = |p: &Point| p.coords.get(axis).copied().unwrap_or(0);
+        coord(q) - coord(&self.points[node])
+    }
+
+    // For every subtree (keyed by its median's position), the circuit all its points
+    // belong to, or None if they're mixed
+    fn uniform_circuits(&self, circuit: &[usize]) -> Vec<Option<usize>> {
+        let mut labels = vec![None; self.order.len()];
+        if !self.order.is_empty() {
+            self.label(0, self.order.len(), circuit, &mut labels);
+        }
+        labels
+    }
+
+    fn label(
+        &self,
+        lo: usize,
+        hi: usize,
+        circuit: &[usize],
+        labels: &mut [Option<usize>],
+    ) -> Option<usize> {
+        let mid = lo + (hi - lo) / 2;
+        let own = circuit[self.order[mid]];
+        let left = if lo < mid {
+            self.label(lo, mid, circuit, labels)
         } else {
-            self.parent[root_y] = root_x;
-            self.size[root_x] += self.size[root_y];
+            Some(own)
+        };
+        let right = if mid + 1 < hi {
+            self.label(mid + 1, hi, circuit, labels)
+        } else {
+            Some(own)
+        };
+        labels[mid] = (left == Some(own) && right == Some(own)).then_some(own);
+        labels[mid]
+    }
+
+    // Improve `best`, a (distance, low, high) link out of query's circuit, with
+    // the closest point in another circuit. Subtrees entirely inside the query's
+    // circuit are skipped
+    fn nearest_outside(
+        &self,
+        query: usize,
+        circuit: &[usize],
+        uniform: &[Option<usize>],
+        best: &mut Option<(i64, usize, usize)>,
+    ) {
+        self.search_outside(query, circuit, uniform, (0, self.order.len(), 0), best);
+    }
+
+    fn search_outside(
+        &self,
+        query: usize,
+        circuit: &[usize],
+        uniform: &[Option<usize>],
+        (lo, hi, depth): (usize, usize, usize),
+        best: &mut Option<(i64, usize, usize)>,
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        if uniform[mid] == Some(circuit[query]) {
+            return;
+        }
+        let node = self.order[mid];
+        let q = &self.points[query];
+
+        if circuit[node] != circuit[query] {
+            let dist = self.metric.distance(q, &self.points[node]);
+            let candidate = (dist, query.min(node), query.max(node));
+            if best.is_none_or(|b| candidate < b) {
+                *best = Some(candidate);
+            }
+        }
+
+        let diff = self.split_diff(q, node, depth);
+        let (near, far) = if diff < 0 {
+            ((lo, mid), (mid + 1, hi))
+        } else {
+            ((mid + 1, hi), (lo, mid))
+        };
+        self.search_outside(query, circuit, uniform, (near.0, near.1, depth + 1), best);
+        // Equal distances still matter, the lower index wins the tie
+        if best.is_none_or(|b| self.metric.axis_bound(diff) <= b.0) {
+            self.search_outside(query, circuit, uniform, (far.0, far.1, depth + 1), best);
+        }
+    }
+}
+
+// A k-nearest search: which point, how many, and the last neighbour already seen
+struct Wanted {
+    query: usize,
+    k: usize,
+    after: Option<(i64, usize)>,
+}
+
+// Lazily yields every pair of points in increasing distance, ties by (i, j),
+// without ever building the full n² edge list
+struct NearestEdges<'a> {
+    tree: KdTree<'a>,
+    // The current batch of each point's neighbours, how far into it we are,
+    // and how many the batch asked for (fewer came back once there are no more)
+    neighbours: Vec<Vec<(i64, usize)>>,
+    cursor: Vec<usize>,
+    batch: Vec<usize>,
+    // Each point's next unused neighbour as (distance, low index, high index, owner)
+    heap: BinaryHeap<Reverse<(i64, usize, usize, usize)>>,
+}
+
+const INITIAL_NEIGHBOURS: usize = 8;
+
+impl<'a> NearestEdges<'a> {
+    fn new(points: &'a [Point], metric: Metric) -> Self {
+        let n = points.len();
+        let mut edges = NearestEdges {
+            tree: KdTree::new(points, metric),
+            neighbours: vec![vec![]; n],
+            cursor: vec![0; n],
+            batch: vec![INITIAL_NEIGHBOURS; n],
+            heap: BinaryHeap::new(),
+        };
+        for i in 0..n {
+            edges.neighbours[i] = edges.tree.nearest_after(i, INITIAL_NEIGHBOURS, None);
+            edges.push_next(i);
+        }
+        edges
+    }
+
+    // Queue the next neighbour of point i. A used-up batch is dropped and the next,
+    // twice as big, starts after its last neighbour
+    fn push_next(&mut self, i: usize) {
+        let used_up = self.cursor[i] == self.neighbours[i].len();
+        if used_up && self.neighbours[i].len() == self.batch[i] {
+            let last = self.neighbours[i].last().copied();
+            self.batch[i] *= 2;
+            self.neighbours[i] = self.tree.nearest_after(i, self.batch[i], last);
+            self.cursor[i] = 0;
+        }
+        if let Some(&(dist, j)) = self.neighbours[i].get(self.cursor[i]) {
+            self.heap.push(Reverse((dist, i.min(j), i.max(j), i)));
         }
-        true
+    }
+}
+
+impl Iterator for NearestEdges<'_> {
+    type Item = Edge;
+
+    fn next(&mut self) -> Option<Edge> {
+        loop {
+            let Reverse((dist, i, j, owner)) = self.heap.pop()?;
+            self.cursor[owner] += 1;
+            self.push_next(owner);
+
+            // Every pair shows up once from each end, keep the one from the lower index
+            if owner == i {
+                return Some(Edge { i, j, dist });
+            }
+        }
+    }
+}
+
+// Minimum spanning tree by Borůvka: each round every circuit links to its nearest
+// other circuit, found with KD-tree searches. Pulling the pair stream until
+// everything connects would need every pair shorter than the longest tree edge,
+// which one far-away box makes all n² of them. Edges come out in Kruskal order
+fn boruvka(points: &[Point], metric: Metric) -> Vec<Edge> {
+    let n = points.len();
+    let tree = KdTree::new(points, metric);
+    let mut uf = UnionFind::new(n);
+    let mut edges = vec![];
+
+    while uf.component_count() > 1 {
+        let circuit: Vec<usize> = (0..n).map(|i| uf.find(i)).collect();
+        let uniform = tree.uniform_circuits(&circuit);
+
+        // Cheapest link out of each circuit, by (distance, low, high) like the stream
+        let mut best: Vec<Option<(i64, usize, usize)>> = vec![None; n];
+        for i in 0..n {
+            tree.nearest_outside(i, &circuit, &uniform, &mut best[circuit[i]]);
+        }
+        for (dist, i, j) in best.into_iter().flatten() {
+            if uf.union(i, j) {
+                edges.push(Edge { i, j, dist });
+            }
+        }
+    }
+    edges.sort_by_key(|e| (e.dist, e.i, e.j));
+    edges
+}
+
+// One join of two circuits while connecting boxes shortest-first
+#[derive(Debug, Clone, Copy)]
+struct Merge {
+    // Cluster ids: points are 0..n, the cluster made by merge k is n + k
+    a: usize,
+    b: usize,
+    edge: Edge,
+    // Connections made so far, counting ones inside an existing circuit.
+    // Only known for merges within the connections pulled from the pair stream
+    connections: Option<usize>,
+    size: usize,
+}
+
+// Single-linkage merge tree, built from the Kruskal merge order
+struct Dendrogram {
+    points: usize,
+    metric: Metric,
+    // Connection counts are known up to here
+    tracked: usize,
+    // The first `tracked` connections, including ones inside a circuit
+    connections: Vec<Edge>,
+    merges: Vec<Merge>,
+    // Largest circuit after the first k merges
+    largest: Vec<usize>,
+    // The merge each cluster id gets absorbed by (usize::MAX for the final one)
+    absorbed: Vec<usize>,
+    // latest[k][i]: cluster id with the largest `absorbed` among ids i..i + 2^k
+    latest: Vec<Vec<usize>>,
+}
+
+impl Dendrogram {
+    // Connection counts are tracked for the first `connections` connections,
+    // the rest of the tree comes from Borůvka
+    fn build(points: &[Point], metric: Metric, connections: usize) -> Dendrogram {
+        let n = points.len();
+
+        // Make the first connections in order, noting which ones join two circuits
+        let mut uf = UnionFind::new(n);
+        let made: Vec<Edge> = NearestEdges::new(points, metric)
+            .take(connections)
+            .collect();
+        let mut joining = vec![];
+        let mut ranks = vec![];
+        for (rank, &edge) in made.iter().enumerate() {
+            if uf.union(edge.i, edge.j) {
+                joining.push(edge);
+                ranks.push(rank + 1);
+            }
+        }
+
+        // Same strict (distance, i, j) order on both sides, so they agree on the tree
+        let complete = uf.component_count() <= 1;
+        let tree = if complete {
+            joining
+        } else {
+            boruvka(points, metric)
+        };
+
+        let mut uf = UnionFind::new(n);
+        // Current cluster id of each root
+        let mut cluster: Vec<usize> = (0..n).collect();
+        let mut merges = vec![];
+        let mut largest = vec![n.min(1)];
+        for (k, edge) in tree.into_iter().enumerate() {
+            let (a, b) = (cluster[uf.find(edge.i)], cluster[uf.find(edge.j)]);
+            uf.union(edge.i, edge.j);
+            let root = uf.find(edge.i);
+            cluster[root] = n + k;
+            let size = uf.size_of(root);
+            merges.push(Merge {
+                a,
+                b,
+                edge,
+                connections: ranks.get(k).copied(),
+                size,
+            });
+            largest.push(size.max(*largest.last().unwrap()));
+        }
+
+        let mut absorbed = vec![usize::MAX; n + merges.len()];
+        for (k, m) in merges.iter().enumerate() {
+            absorbed[m.a] = k;
+            absorbed[m.b] = k;
+        }
+        let mut latest = vec![(0..absorbed.len()).collect::<Vec<usize>>()];
+        let mut width = 1;
+        while 2 * width <= absorbed.len() {
+            let prev = latest.last().unwrap();
+            let level: Vec<usize> = (0..=absorbed.len() - 2 * width)
+                .map(|i| {
+                    let (a, b) = (prev[i], prev[i + width]);
+                    if absorbed[b] > absorbed[a] { b } else { a }
+                })
+                .collect();
+            latest.push(level);
+            width *= 2;
+        }
+
+        Dendrogram {
+            points: n,
+            metric,
+            tracked: if complete { usize::MAX } else { connections },
+            connections: made,
+            merges,
+            largest,
+            absorbed,
+            latest,
+        }
+    }
+
+    // Merges done within the first `connections` shortest connections
+    fn merges_within_connections(&self, connections: usize) -> usize {
+        assert!(
+            connections <= self.tracked,
+            "Only the first {} connections were tracked",
+            self.tracked
+        );
+        self.merges
+            .partition_point(|m| m.connections.is_some_and(|c| c <= connections))
+    }
+
+    // Merges done using connections no longer than the cutoff (stored units)
+    fn merges_within_distance(&self, max_dist: i64) -> usize {
+        self.merges.partition_point(|m| m.edge.dist <= max_dist)
+    }
+
+    fn circuit_count(&self, merges: usize) -> usize {
+        self.points - merges
+    }
+
+    fn largest_circuit(&self, merges: usize) -> usize {
+        self.largest[merges]
+    }
+
+    // Cluster id absorbed last among ids lo..hi
+    fn latest_in(&self, lo: usize, hi: usize) -> usize {
+        let level = (hi - lo).ilog2() as usize;
+        let (a, b) = (
+            self.latest[level][lo],
+            self.latest[level][hi - (1 << level)],
+        );
+        if self.absorbed[b] > self.absorbed[a] {
+            b
+        } else {
+            a
+        }
+    }
+
+    // Every circuit size after the first `merges` merges, largest first.
+    // The circuits alive then are the clusters made so far that aren't absorbed yet.
+    // The one absorbed last in a span either is alive, and both sides of it get
+    // searched, or nothing in the span is. That's O(circuits) plus the sort, not
+    // O(log n): use circuit_count and largest_circuit when those are enough
+    fn circuit_sizes(&self, merges: usize) -> Vec<usize> {
+        let mut sizes = vec![];
+        let mut spans = vec![(0, self.points + merges)];
+        while let Some((lo, hi)) = spans.pop() {
+            if lo >= hi {
+                continue;
+            }
+            let id = self.latest_in(lo, hi);
+            if self.absorbed[id] < merges {
+                continue;
+            }
+            sizes.push(if id < self.points {
+                1
+            } else {
+                self.merges[id - self.points].size
+            });
+            spans.push((lo, id));
+            spans.push((id + 1, hi));
+        }
+        sizes.sort_by(|a, b| b.cmp(a));
+        sizes
+    }
+
+    // Linkage table in the usual (a, b, distance, size) layout for plotting
+    fn to_csv(&self) -> String {
+        let mut out = String::from("cluster,a,b,distance,size,connections\n");
+        for (k, m) in self.merges.iter().enumerate() {
+            out.push_str(&format!(
+                "{},{},{},{:.3},{},{}\n",
+                self.points + k,
+                m.a,
+                m.b,
+                self.metric.length(m.edge.dist),
+                m.size,
+                m.connections.map_or(String::new(), |c| c.to_string())
+            ));
+        }
+        out
     }
 }
 
 // Main algorithm
-fn solve(points: &[Point], max_connections: usize) -> Vec<usize> {
-    let edges = compute_all_edges(points);
-    let mut uf = UnionFind::new(points.len());
+// The example wants 10 connections, real inputs (1000 boxes) want 1000
+fn default_connections(points: usize) -> usize {
+    if points <= 20 { 10 } else { 1000 }
+}
 
-    for edge in edges.iter().take(max_connections) {
-        uf.union(edge.i, edge.j);
+// Circuits after a number of connections
+#[derive(Debug)]
+struct CircuitReport {
+    // Every circuit size, largest first
+    sizes: Vec<usize>,
+    // Product of the top K sizes
+    product: usize,
+    // Every connection made, shortest first (all of them, unless there are
+    // fewer pairs than that)
+    edges_used: Vec<Edge>,
+    // The ones among them that joined two circuits
+    merging_edges: Vec<Edge>,
+}
+
+fn solve(dendrogram: &Dendrogram, max_connections: usize, top: usize) -> CircuitReport {
+    let merges = dendrogram.merges_within_connections(max_connections);
+    let sizes = dendrogram.circuit_sizes(merges);
+    let product = sizes.iter().take(top).product();
+    CircuitReport {
+        sizes,
+        product,
+        edges_used: dendrogram.connections[..max_connections.min(dendrogram.connections.len())]
+            .to_vec(),
+        merging_edges: dendrogram.merges[..merges].iter().map(|m| m.edge).collect(),
     }
+}
 
-    // Debug
-    // println!("Parents: {:?}", &uf.parent[..20.min(uf.parent.len())]);
-    // println!("Sizes: {:?}", &uf.size[..20.min(uf.size.len())]);
-
-    // Collect circuit sizes (only count each root once)
-    let mut sizes: Vec<usize> = (0..points.len())
-        .filter_map(|i| {
-            let root = uf.find(i);
-            if root == i { Some(uf.size[i]) } else { None }
-        })
-        .collect();
+// The connection that finally joins everything, and the product of its x values
+fn solve_part2(points: &[Point], dendrogram: &Dendrogram) -> (Edge, i64) {
+    // Kruskal adds the edge that finally connects everything last
+    let merge = dendrogram
+        .merges
+        .last()
+        .expect("Should have found a connecting edge");
+    let edge = merge.edge;
+    (edge, points[edge.i].coords[0] * points[edge.j].coords[0])
+}
 
-    // Sort descending
-    sizes.sort_by(|a, b| b.cmp(a));
-    sizes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MstAlgorithm {
+    Kruskal,
+    Prim,
 }
 
-fn solve_part2(points: &[Point]) -> i64 {
-    let edges = compute_all_edges(points);
-    let mut uf = UnionFind::new(points.len());
+// Minimum spanning tree over all junction boxes
+struct SpanningTree {
+    edges: Vec<Edge>,
+    total_length: f64,
+    longest: Option<Edge>,
+}
 
-    let mut circuits_remaining = points.len();
+// Edges in the order Kruskal accepts them, which are the dendrogram's merges
+fn kruskal(dendrogram: &Dendrogram) -> Vec<Edge> {
+    dendrogram.merges.iter().map(|m| m.edge).collect()
+}
 
-    let mut last_edge: Option<&Edge> = None;
+// Dense O(n²) Prim, grows the tree from point 0 without needing an edge list
+fn prim(points: &[Point], metric: Metric) -> Vec<Edge> {
+    let n = points.len();
+    let mut in_tree = vec![false; n];
+    // Cheapest known link into the tree for every point outside it: (distance, tree point)
+    let mut best: Vec<(i64, usize)> = vec![(i64::MAX, 0); n];
+    let mut edges = Vec::with_capacity(n.saturating_sub(1));
 
-    for edge in &edges {
-        if uf.union(edge.i, edge.j) {
-            circuits_remaining -= 1;
-            last_edge = Some(edge);
+    if n == 0 {
+        return edges;
+    }
+    let mut current = 0;
+    in_tree[0] = true;
 
-            if circuits_remaining == 1 {
-                break; // Everything is connected
+    for _ in 1..n {
+        for j in 0..n {
+            if !in_tree[j] {
+                let d = metric.distance(&points[current], &points[j]);
+                if d < best[j].0 {
+                    best[j] = (d, current);
+                }
             }
         }
+
+        let next = (0..n)
+            .filter(|&j| !in_tree[j])
+            .min_by_key(|&j| (best[j].0, j))
+            .unwrap();
+        let (dist, from) = best[next];
+        edges.push(Edge {
+            i: from.min(next),
+            j: from.max(next),
+            dist,
+        });
+        in_tree[next] = true;
+        current = next;
+    }
+    edges
+}
+
+fn minimum_spanning_tree(
+    points: &[Point],
+    dendrogram: &Dendrogram,
+    algorithm: MstAlgorithm,
+) -> SpanningTree {
+    let metric = dendrogram.metric;
+    let edges = match algorithm {
+        MstAlgorithm::Kruskal => kruskal(dendrogram),
+        MstAlgorithm::Prim => prim(points, metric),
+    };
+    let total_length = edges.iter().map(|e| metric.length(e.dist)).sum();
+    let longest = edges.iter().copied().max_by_key(|e| e.dist);
+    SpanningTree {
+        edges,
+        total_length,
+        longest,
+    }
+}
+
+fn join_coords(p: &Point) -> String {
+    let coords: Vec<String> = p.coords.iter().map(|c| c.to_string()).collect();
+    coords.join(",")
+}
+
+// Graphviz DOT, nodes pinned at their first two coordinates
+fn mst_to_dot(points: &[Point], tree: &SpanningTree, metric: Metric) -> String {
+    let mut out = String::from("graph circuits {\n    node [shape=point];\n");
+    for (i, p) in points.iter().enumerate() {
+        let x = p.coords.first().copied().unwrap_or(0);
+        let y = p.coords.get(1).copied().unwrap_or(0);
+        out.push_str(&format!(
+            "    {} [pos=\"{},{}!\", tooltip=\"{}\"];\n",
+            i,
+            x,
+            y,
+            join_coords(p)
+        ));
+    }
+    for edge in &tree.edges {
+        out.push_str(&format!(
+            "    {} -- {} [len={:.3}];\n",
+            edge.i,
+            edge.j,
+            metric.length(edge.dist)
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn mst_to_csv(points: &[Point], tree: &SpanningTree, metric: Metric) -> String {
+    // a0,a1,... then b0,b1,... for however many dimensions there are
+    let dims = points.first().map_or(0, Point::dims);
+    let mut header = vec!["i".to_string(), "j".to_string()];
+    for end in ["a", "b"] {
+        header.extend((0..dims).map(|k| format!("{}{}", end, k)));
+    }
+    header.push("length".to_string());
+
+    let mut out = header.join(",") + "\n";
+    for edge in &tree.edges {
+        out.push_str(&format!(
+            "{},{},{},{},{:.3}\n",
+            edge.i,
+            edge.j,
+            join_coords(&points[edge.i]),
+            join_coords(&points[edge.j]),
+            metric.length(edge.dist)
+        ));
     }
+    out
+}
+
+// Command line options
+struct Options {
+    metric: Metric,
+    mst: MstAlgorithm,
+    dot_path: Option<String>,
+    csv_path: Option<String>,
+    dendrogram_path: Option<String>,
+    cutoff: Option<f64>,
+    connections: Option<usize>,
+    top: usize,
+}
 
-    let edge = last_edge.expect("Should have found a connecting edge");
-    points[edge.i].x * points[edge.j].x
+fn parse_args() -> Options {
+    let mut options = Options {
+        metric: Metric::Euclidean,
+        mst: MstAlgorithm::Kruskal,
+        dot_path: None,
+        csv_path: None,
+        dendrogram_path: None,
+        cutoff: None,
+        connections: None,
+        top: 3,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| panic!("{} needs a value", arg))
+        };
+        match arg.as_str() {
+            "--metric" => {
+                let name = value();
+                options.metric = Metric::parse(&name).unwrap_or_else(|| {
+                    panic!(
+                        "Unknown metric '{}' (euclidean, manhattan or chebyshev)",
+                        name
+                    )
+                })
+            }
+            "--mst" => {
+                options.mst = match value().as_str() {
+                    "kruskal" => MstAlgorithm::Kruskal,
+                    "prim" => MstAlgorithm::Prim,
+                    other => panic!("Unknown MST algorithm '{}' (kruskal or prim)", other),
+                }
+            }
+            "--mst-dot" => options.dot_path = Some(value()),
+            "--mst-csv" => options.csv_path = Some(value()),
+            "--dendrogram" => options.dendrogram_path = Some(value()),
+            "--cutoff" => {
+                options.cutoff = Some(value().parse().expect("Invalid --cutoff distance"))
+            }
+            "--connections" => {
+                options.connections = Some(value().parse().expect("Invalid --connections count"))
+            }
+            "--top" => options.top = value().parse().expect("Invalid --top count"),
+            other => panic!("Unknown option '{}'", other),
+        }
+    }
+    options
 }
 
 fn main() {
+    let options = parse_args();
     let input = read_input();
     let points = parse_points(&input);
 
-    println!("Parsed {} points", points.len());
+    let metric = options.metric;
+    println!(
+        "Parsed {} points in {} dimensions ({:?} distance)",
+        points.len(),
+        points.first().map_or(0, Point::dims),
+        metric
+    );
 
-    let circuit_sizes = solve(&points, 1000); // Stop at 1000
+    let connections = options
+        .connections
+        .unwrap_or_else(|| default_connections(points.len()));
+    // Every question below is answered from the one merge tree
+    let dendrogram = Dendrogram::build(&points, metric, connections);
+    let report = solve(&dendrogram, connections, options.top);
 
     // Show top circuits (for debuggging)
+    println!(
+        "After {} connections: {} circuits, {} connections joined two of them",
+        report.edges_used.len(),
+        report.sizes.len(),
+        report.merging_edges.len()
+    );
     println!(
         "Top circuit sizes: {:?}",
-        &circuit_sizes[..circuit_sizes.len().min(10)]
+        &report.sizes[..report.sizes.len().min(10)]
     );
 
-    // Part 1: product of largest three
-    let part1: usize = circuit_sizes.iter().take(3).product();
-    println!("Part 1: {}", part1);
+    // Part 1: product of the largest K
+    println!("Part 1 (top {}): {}", options.top, report.product);
 
     // Part 2
-    let part2 = solve_part2(&points);
+    let (last, part2) = solve_part2(&points, &dendrogram);
+    // Debug
+    println!(
+        "Last connection: {} - {} (distance {:.3})",
+        last.i,
+        last.j,
+        metric.length(last.dist)
+    );
     println!("Part 2: {}", part2);
+
+    // Full wiring
+    let tree = minimum_spanning_tree(&points, &dendrogram, options.mst);
+    println!(
+        "\nMinimum spanning tree ({:?}): {} edges, total length {:.3}",
+        options.mst,
+        tree.edges.len(),
+        tree.total_length
+    );
+    if let Some(edge) = tree.longest {
+        println!(
+            "Longest edge: {} - {} (length {:.3})",
+            edge.i,
+            edge.j,
+            metric.length(edge.dist)
+        );
+    }
+    if let Some(path) = &options.dot_path {
+        std::fs::write(path, mst_to_dot(&points, &tree, metric)).expect("Could not write DOT file");
+        println!("Wrote {}", path);
+    }
+    if let Some(path) = &options.csv_path {
+        std::fs::write(path, mst_to_csv(&points, &tree, metric)).expect("Could not write CSV file");
+        println!("Wrote {}", path);
+    }
+
+    // Circuits for any distance cutoff
+    if let Some(cutoff) = options.cutoff {
+        let merges = dendrogram.merges_within_distance(metric.cutoff(cutoff));
+        println!(
+            "\nConnections up to length {}: {} circuits, largest has {} boxes",
+            cutoff,
+            dendrogram.circuit_count(merges),
+            dendrogram.largest_circuit(merges)
+        );
+    }
+    if let Some(path) = &options.dendrogram_path {
+        std::fs::write(path, dendrogram.to_csv()).expect("Could not write dendrogram");
+        println!("Wrote {}", path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+162,817,812
+57,618,57
+906,360,560
+592,479,940
+352,342,300
+466,668,158
+542,29,236
+431,825,988
+739,650,466
+52,470,668
+216,146,977
+819,987,18
+117,168,530
+805,96,715
+346,949,466
+970,615,88
+941,993,340
+862,61,35
+984,92,344
+425,690,689
+";
+
+    // Reference ordering: every pair, sorted by distance then (i, j)
+    fn brute_force_edges(points: &[Point], metric: Metric) -> Vec<(i64, usize, usize)> {
+        let mut edges = vec![];
+        for i in 0..points.len() {
+            for j in (i + 1)..points.len() {
+                edges.push((metric.distance(&points[i], &points[j]), i, j));
+            }
+        }
+        edges.sort();
+        edges
+    }
+
+    fn lazy_edges(points: &[Point], metric: Metric) -> Vec<(i64, usize, usize)> {
+        NearestEdges::new(points, metric)
+            .map(|e| (e.dist, e.i, e.j))
+            .collect()
+    }
+
+    #[test]
+    fn test_example() {
+        let points = parse_points(EXAMPLE);
+        let connections = default_connections(points.len());
+        assert_eq!(connections, 10);
+        let dendrogram = Dendrogram::build(&points, Metric::Euclidean, 10);
+        let report = solve(&dendrogram, connections, 3);
+        assert_eq!(report.product, 40);
+        assert_eq!(report.sizes.iter().sum::<usize>(), points.len());
+        assert_eq!(report.edges_used.len(), connections);
+        assert_eq!(
+            report.merging_edges.len(),
+            points.len() - report.sizes.len()
+        );
+        assert_eq!(solve(&dendrogram, connections, 1).product, 5);
+        assert_eq!(solve_part2(&points, &dendrogram).1, 25272);
+    }
+
+    #[test]
+    fn test_dendrogram_queries() {
+        let points = parse_points(EXAMPLE);
+        let dendrogram = Dendrogram::build(&points, Metric::Euclidean, 10);
+        assert_eq!(dendrogram.merges.len(), points.len() - 1);
+
+        // Ten connections leave 11 circuits, the largest with 5 boxes
+        let merges = dendrogram.merges_within_connections(10);
+        assert_eq!(dendrogram.circuit_count(merges), 11);
+        assert_eq!(dendrogram.largest_circuit(merges), 5);
+        assert_eq!(dendrogram.circuit_sizes(merges)[..3], [5, 4, 2]);
+
+        // Cutting at any merge distance agrees with a replay
+        let mut uf = UnionFind::new(points.len());
+        for m in &dendrogram.merges {
+            uf.union(m.edge.i, m.edge.j);
+            let mut replayed = uf.component_sizes();
+            replayed.sort_by(|a, b| b.cmp(a));
+
+            let merges = dendrogram.merges_within_distance(m.edge.dist);
+            let sizes = dendrogram.circuit_sizes(merges);
+            assert_eq!(sizes, replayed);
+            assert_eq!(sizes.len(), dendrogram.circuit_count(merges));
+            assert_eq!(sizes[0], dendrogram.largest_circuit(merges));
+        }
+        assert_eq!(dendrogram.merges.last().unwrap().size, points.len());
+    }
+
+    #[test]
+    fn test_prim_matches_kruskal() {
+        let points = parse_points(EXAMPLE);
+        for metric in [Metric::Euclidean, Metric::Manhattan, Metric::Chebyshev] {
+            let dendrogram = Dendrogram::build(&points, metric, 10);
+            let k = minimum_spanning_tree(&points, &dendrogram, MstAlgorithm::Kruskal);
+            let p = minimum_spanning_tree(&points, &dendrogram, MstAlgorithm::Prim);
+
+            assert_eq!(k.edges.len(), points.len() - 1);
+            assert_eq!(p.edges.len(), points.len() - 1);
+            assert!((k.total_length - p.total_length).abs() < 1e-6);
+            assert_eq!(k.longest.unwrap().dist, p.longest.unwrap().dist);
+        }
+
+        let dendrogram = Dendrogram::build(&points, Metric::Euclidean, 10);
+        let k = minimum_spanning_tree(&points, &dendrogram, MstAlgorithm::Kruskal);
+        let csv = mst_to_csv(&points, &k, Metric::Euclidean);
+        assert_eq!(csv.lines().count(), points.len());
+        assert!(csv.starts_with("i,j,a0,a1,a2,b0,b1,b2,length"));
+        assert!(mst_to_dot(&points, &k, Metric::Euclidean).contains(" -- "));
+    }
+
+    #[test]
+    fn test_nearest_edges_match_brute_force() {
+        let points = parse_points(EXAMPLE);
+        // Small lattice, lots of equal distances
+        let mut lattice = vec![];
+        for x in 0..4 {
+            for y in 0..3 {
+                for z in 0..3 {
+                    lattice.push(Point {
+                        coords: vec![x, y, z],
+                    });
+                }
+            }
+        }
+        // A 2D floor plan
+        let plan = parse_points("0,0\n5,1\n2,7\n9,9\n3,3\n8,0\n1,4\n");
+
+        for metric in [Metric::Euclidean, Metric::Manhattan, Metric::Chebyshev] {
+            for set in [&points, &lattice, &plan] {
+                let brute = brute_force_edges(set, metric);
+                assert_eq!(lazy_edges(set, metric), brute);
+
+                // Borůvka has to land on exactly the connections Kruskal makes
+                let mut uf = UnionFind::new(set.len());
+                let kruskal: Vec<_> = brute
+                    .into_iter()
+                    .filter(|&(_, i, j)| uf.union(i, j))
+                    .collect();
+                let tree: Vec<_> = boruvka(set, metric)
+                    .into_iter()
+                    .map(|e| (e.dist, e.i, e.j))
+                    .collect();
+                assert_eq!(tree, kruskal);
+            }
+        }
+    }
+
+    #[test]
+    fn test_far_outlier() {
+        // One box miles away from everything else. Connecting everything through
+        // the pair stream would have to go through nearly every pair first
+        let mut seed: u64 = 42;
+        let mut next = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) % 100_000) as i64
+        };
+        let mut points: Vec<Point> = (0..3000)
+            .map(|_| Point {
+                coords: vec![next(), next(), next()],
+            })
+            .collect();
+        points.push(Point {
+            coords: vec![100_000_000, 0, 0],
+        });
+
+        let dendrogram = Dendrogram::build(&points, Metric::Euclidean, 1000);
+        let (last, _) = solve_part2(&points, &dendrogram);
+        assert_eq!(last.j, 3000);
+        assert_eq!(dendrogram.merges.len(), points.len() - 1);
+        assert!(dendrogram.merges_within_connections(1000) <= 1000);
+
+        let k = minimum_spanning_tree(&points, &dendrogram, MstAlgorithm::Kruskal);
+        let p = minimum_spanning_tree(&points, &dendrogram, MstAlgorithm::Prim);
+        assert!((k.total_length - p.total_length).abs() < 1e-6);
+    }
+
+    #[test]
+    #[should_panic(expected = "Point 2 has 2 coordinates, expected 3")]
+    fn test_mixed_dimensions() {
+        parse_points("1,2,3\n4,5\n");
+    }
 }
@@ -1,3 +1,4 @@
+use std::cmp::Reverse;
 use std::collections::HashMap;
 use std::fs::read_to_string;
 
@@ -10,7 +11,7 @@ fn read_input() -> String {
         .expect("Could not find input.txt")
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 struct Point {
     x: i64,
     y: i64,
@@ -30,164 +31,490 @@ fn parse_points(input: &str) -> Vec<Point> {
         .collect()
 }
 
-// Part 1: find max rectangle
-fn find_max_rectangle(points: &[Point]) -> (Point, Point, i64) {
-    let mut max_area = 0;
-    let mut best_pair = (points[0], points[0]);
+// Rectangle with red tiles in two opposite corners
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rectangle {
+    a: Point,
+    b: Point,
+    area: i64,
+}
+
+impl Rectangle {
+    fn new(a: Point, b: Point) -> Self {
+        let area = ((a.x - b.x).abs() + 1) * ((a.y - b.y).abs() + 1);
+        Rectangle { a, b, area }
+    }
+
+    // A single row or column of tiles
+    fn is_degenerate(&self) -> bool {
+        self.a.x == self.b.x || self.a.y == self.b.y
+    }
+
+    // Short side over long side, 1.0 for a square
+    fn aspect(&self) -> f64 {
+        let width = (self.a.x - self.b.x).abs() + 1;
+        let height = (self.a.y - self.b.y).abs() + 1;
+        width.min(height) as f64 / width.max(height) as f64
+    }
+
+    fn contains(&self, p: &Point) -> bool {
+        self.a.x.min(self.b.x) <= p.x
+            && p.x <= self.a.x.max(self.b.x)
+            && self.a.y.min(self.b.y) <= p.y
+            && p.y <= self.a.y.max(self.b.y)
+    }
+}
 
+// Which rectangles count, and how many to report
+struct RectangleOptions {
+    top_k: usize,
+    allow_degenerate: bool,
+    min_aspect: f64,
+    must_contain: Option<Point>,
+}
+
+impl Default for RectangleOptions {
+    fn default() -> Self {
+        RectangleOptions {
+            top_k: 1,
+            allow_degenerate: false,
+            min_aspect: 0.0,
+            must_contain: None,
+        }
+    }
+}
+
+// Every pair of points that passes the constraints, biggest first
+fn candidate_rectangles(points: &[Point], options: &RectangleOptions) -> Vec<Rectangle> {
+    let mut candidates = vec![];
     for i in 0..points.len() {
         for j in (i + 1)..points.len() {
-            let p1 = &points[i];
-            let p2 = &points[j];
-
-            // Must be diagonal (different x and different y)
-            if p1.x != p2.x && p1.y != p2.y {
-                let width = (p1.x - p2.x).abs() + 1;
-                let height = (p1.y - p2.y).abs() + 1;
-                let area = width * height;
-
-                if area > max_area {
-                    max_area = area;
-                    best_pair = (*p1, *p2);
-                }
+            let rect = Rectangle::new(points[i], points[j]);
+            if rect.is_degenerate() && !options.allow_degenerate {
+                continue;
+            }
+            if rect.aspect() < options.min_aspect {
+                continue;
             }
+            if let Some(p) = &options.must_contain
+                && !rect.contains(p)
+            {
+                continue;
+            }
+            candidates.push(rect);
         }
     }
+    // Stable, so equal areas keep input order
+    candidates.sort_by_key(|r| Reverse(r.area));
+    candidates
+}
 
-    (best_pair.0, best_pair.1, max_area)
+// Part 1: find the biggest rectangles
+fn find_max_rectangles(points: &[Point], options: &RectangleOptions) -> Vec<Rectangle> {
+    let mut best = candidate_rectangles(points, options);
+    best.truncate(options.top_k);
+    best
 }
 
 // Part 2: Needed help from reddit
-// Rasterization with flood fill instead of ray casting
-
-fn solve_p2(points: &[Point]) -> i64 {
-    // Coordinate compression
-    let mut uniq_x: Vec<i64> = points.iter().map(|p| p.x).collect();
-    let mut uniq_y: Vec<i64> = points.iter().map(|p| p.y).collect();
-    uniq_x.sort();
-    uniq_x.dedup();
-    uniq_y.sort();
-    uniq_y.dedup();
-
-    let x_map: HashMap<i64, usize> = uniq_x.iter().enumerate().map(|(i, &x)| (x, i)).collect();
-    let y_map: HashMap<i64, usize> = uniq_y.iter().enumerate().map(|(i, &y)| (y, i)).collect();
-
-    // Build grid of points
-    let height = uniq_y.len();
-    let width = uniq_x.len();
-    let mut grid = vec![vec!['.'; width]; height];
-
-    // Rasterize polygon edges
-    for i in 0..points.len() {
-        let a = &points[i];
-        let b = &points[(i + 1) % points.len()];
+// Compressed grid for the O(1) rectangle checks, with every cell classified
+// exactly against the polygon instead of flood filled
+
+// Which way the red tiles go around, taking y as pointing up
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Orientation {
+    Clockwise,
+    CounterClockwise,
+}
 
-        let ax = x_map[&a.x];
-        let ay = y_map[&a.y];
-        let bx = x_map[&b.x];
-        let by = y_map[&b.y];
+// Something wrong with the red tile loop, with the vertex indices involved
+#[derive(Debug, Clone, PartialEq)]
+enum PolygonIssue {
+    TooFewVertices(usize),
+    // The last vertex repeats the first instead of the loop closing by itself
+    DuplicateClosingVertex(usize),
+    ZeroLengthEdge(usize, usize),
+    DiagonalEdge(usize, usize),
+    // The edges either side of the vertex run back along each other
+    Backtrack(usize),
+    // Two edges that aren't neighbours touch or cross, each given by its two vertices
+    SelfIntersection((usize, usize), (usize, usize)),
+    ZeroArea,
+}
 
-        if ax == bx {
-            // Vertical edge
-            let (y1, y2) = if ay < by { (ay, by) } else { (by, ay) };
-            for y in y1..=y2 {
-                grid[y][ax] = '#';
+impl std::fmt::Display for PolygonIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PolygonIssue::TooFewVertices(n) => {
+                write!(f, "polygon needs at least 4 vertices, got {}", n)
+            }
+            PolygonIssue::DuplicateClosingVertex(i) => {
+                write!(
+                    f,
+                    "vertex {} repeats vertex 0, the loop closes by itself",
+                    i
+                )
             }
-        } else if ay == by {
-            // Horizontal edge
-            let (x1, x2) = if ax < bx { (ax, bx) } else { (bx, ax) };
-            for x in x1..=x2 {
-                grid[ay][x] = '#';
+            PolygonIssue::ZeroLengthEdge(i, j) => {
+                write!(f, "edge from vertex {} to {} has zero length", i, j)
             }
+            PolygonIssue::DiagonalEdge(i, j) => {
+                write!(f, "edge from vertex {} to {} is not axis-aligned", i, j)
+            }
+            PolygonIssue::Backtrack(i) => {
+                write!(f, "edges double back on themselves at vertex {}", i)
+            }
+            PolygonIssue::SelfIntersection((a, b), (c, d)) => {
+                write!(f, "edge {}-{} touches edge {}-{}", a, b, c, d)
+            }
+            PolygonIssue::ZeroArea => write!(f, "polygon encloses no area"),
         }
     }
+}
 
-    println!("After rasterization:");
-    print_grid(&grid, 20);
-
-    // Flood fill
-    let inside_pt = get_inside_point(&grid);
-    flood_fill(&mut grid, inside_pt);
-
-    println!("After flood fill:");
-    print_grid(&grid, 20);
-
-    // Check all rectangle pairs
-    let mut max_area = 0;
-    for i in 0..points.len() {
-        for j in (i + 1)..points.len() {
-            let p1 = &points[i];
-            let p2 = &points[j];
+// Everything that stops the points forming a simple rectilinear loop
+fn validate_polygon(points: &[Point]) -> Vec<PolygonIssue> {
+    let n = points.len();
+    if n < 4 {
+        return vec![PolygonIssue::TooFewVertices(n)];
+    }
+    let mut issues = vec![];
+    for i in 0..n {
+        let j = (i + 1) % n;
+        let (prev, cur, next) = (points[(i + n - 1) % n], points[i], points[j]);
+        if cur == next {
+            issues.push(if j == 0 {
+                PolygonIssue::DuplicateClosingVertex(i)
+            } else {
+                PolygonIssue::ZeroLengthEdge(i, j)
+            });
+        } else if cur.x != next.x && cur.y != next.y {
+            issues.push(PolygonIssue::DiagonalEdge(i, j));
+        }
+        // Both edges on the same line but pointing opposite ways
+        let (ax, ay) = (cur.x - prev.x, cur.y - prev.y);
+        let (bx, by) = (next.x - cur.x, next.y - cur.y);
+        if ax * by - ay * bx == 0 && ax * bx + ay * by < 0 {
+            issues.push(PolygonIssue::Backtrack(i));
+        }
+    }
 
-            if p1.x != p2.x && p1.y != p2.y {
-                if is_enclosed(p1, p2, &grid, &x_map, &y_map) {
-                    let area = ((p1.x - p2.x).abs() + 1) * ((p1.y - p2.y).abs() + 1);
-                    max_area = max_area.max(area);
+    // Repeated vertices make neighbouring edges look like they touch, so only
+    // look for crossings once those are sorted out
+    if issues.iter().all(|issue| {
+        !matches!(
+            issue,
+            PolygonIssue::ZeroLengthEdge(..) | PolygonIssue::DuplicateClosingVertex(_)
+        )
+    }) {
+        for i in 0..n {
+            // Skip the edge itself and both neighbours, and the pair (0, n - 1)
+            for j in (i + 2)..n {
+                if i == 0 && j == n - 1 {
+                    continue;
+                }
+                let (a, b) = (points[i], points[(i + 1) % n]);
+                let (c, d) = (points[j], points[(j + 1) % n]);
+                if segments_touch(a, b, c, d) {
+                    issues.push(PolygonIssue::SelfIntersection((i, i + 1), (j, (j + 1) % n)));
                 }
             }
         }
     }
-    max_area
+
+    let doubled_area: i64 = (0..n)
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum();
+    if doubled_area == 0 {
+        issues.push(PolygonIssue::ZeroArea);
+    }
+    issues
+}
+
+// Closed segments ab and cd share at least one point (works for diagonals too)
+fn segments_touch(a: Point, b: Point, c: Point, d: Point) -> bool {
+    let cross = |o: Point, p: Point, q: Point| {
+        ((p.x - o.x) * (q.y - o.y) - (p.y - o.y) * (q.x - o.x)).signum()
+    };
+    let on_segment = |p: Point, q: Point, r: Point| {
+        p.x.min(q.x) <= r.x && r.x <= p.x.max(q.x) && p.y.min(q.y) <= r.y && r.y <= p.y.max(q.y)
+    };
+    let (d1, d2) = (cross(c, d, a), cross(c, d, b));
+    let (d3, d4) = (cross(a, b, c), cross(a, b, d));
+    if d1 * d2 < 0 && d3 * d4 < 0 {
+        return true;
+    }
+    (d1 == 0 && on_segment(c, d, a))
+        || (d2 == 0 && on_segment(c, d, b))
+        || (d3 == 0 && on_segment(a, b, c))
+        || (d4 == 0 && on_segment(a, b, d))
 }
 
-fn flood_fill(grid: &mut Vec<Vec<char>>, start: Point) {
-    let mut stack = vec![start];
-    let dirs = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+// Exact geometry on the closed loop of red tiles
+struct Polygon {
+    vertices: Vec<Point>,
+}
 
-    while let Some(p) = stack.pop() {
-        if grid[p.y as usize][p.x as usize] != '.' {
-            continue;
+impl Polygon {
+    fn new(points: &[Point]) -> Result<Polygon, Vec<PolygonIssue>> {
+        let issues = validate_polygon(points);
+        if !issues.is_empty() {
+            return Err(issues);
         }
-        grid[p.y as usize][p.x as usize] = 'X';
+        Ok(Polygon {
+            vertices: points.to_vec(),
+        })
+    }
 
-        for (dx, dy) in &dirs {
-            let nx = p.x + dx;
-            let ny = p.y + dy;
-            if ny >= 0
-                && (ny as usize) < grid.len()
-                && nx >= 0
-                && (nx as usize) < grid[0].len()
-                && grid[ny as usize][nx as usize] == '.'
-            {
-                stack.push(Point { x: nx, y: ny });
-            }
+    fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        let n = self.vertices.len();
+        (0..n).map(move |i| (self.vertices[i], self.vertices[(i + 1) % n]))
+    }
+
+    // Shoelace formula, positive when counter-clockwise
+    fn doubled_area(&self) -> i64 {
+        self.edges().map(|(a, b)| a.x * b.y - b.x * a.y).sum()
+    }
+
+    fn orientation(&self) -> Orientation {
+        if self.doubled_area() > 0 {
+            Orientation::CounterClockwise
+        } else {
+            Orientation::Clockwise
         }
     }
-}
 
-fn get_inside_point(grid: &Vec<Vec<char>>) -> Point {
-    for y in 0..grid.len() {
-        for x in 0..grid[0].len() {
-            if grid[y][x] != '.' {
-                continue;
+    // The boundary traced around the outside of its tiles, each vertex pushed half
+    // a tile outwards from both of its edges
+    fn outline(&self) -> Vec<(f64, f64)> {
+        let n = self.vertices.len();
+        let sign = match self.orientation() {
+            Orientation::CounterClockwise => 1,
+            Orientation::Clockwise => -1,
+        };
+        // Unit normal pointing away from the inside
+        let outward = |a: &Point, b: &Point| {
+            let (dx, dy) = ((b.x - a.x).signum(), (b.y - a.y).signum());
+            (sign * dy, -sign * dx)
+        };
+        (0..n)
+            .map(|i| {
+                let (prev, v, next) = (
+                    &self.vertices[(i + n - 1) % n],
+                    &self.vertices[i],
+                    &self.vertices[(i + 1) % n],
+                );
+                let (n_in, n_out) = (outward(prev, v), outward(v, next));
+                // A vertex in the middle of a straight run only moves once
+                let (ox, oy) = if n_in == n_out {
+                    n_in
+                } else {
+                    (n_in.0 + n_out.0, n_in.1 + n_out.1)
+                };
+                (v.x as f64 + ox as f64 / 2.0, v.y as f64 + oy as f64 / 2.0)
+            })
+            .collect()
+    }
+
+    // Point given in doubled coordinates so halfway points stay integers.
+    // Inside or on the boundary both count
+    fn contains(&self, x2: i64, y2: i64) -> bool {
+        let mut inside = false;
+        for (a, b) in self.edges() {
+            let (ax, ay, bx, by) = (2 * a.x, 2 * a.y, 2 * b.x, 2 * b.y);
+            let on_x = ax.min(bx) <= x2 && x2 <= ax.max(bx);
+            let on_y = ay.min(by) <= y2 && y2 <= ay.max(by);
+            if on_x && on_y {
+                return true; // On this edge
+            }
+            // Ray to the right, half-open in y so vertices only count once
+            if ax == bx && ax > x2 && (ay <= y2) != (by <= y2) {
+                inside = !inside;
             }
+        }
+        inside
+    }
+
+    // Is any tile of the rectangle outside the polygon? Walking from a tile inside
+    // to one outside always steps off an edge, so only the lines of tiles right
+    // alongside each edge need checking. Gaps narrower than a tile don't count
+    fn reaches_outside(&self, a: &Point, b: &Point) -> bool {
+        let (x1, x2) = (a.x.min(b.x), a.x.max(b.x));
+        let (y1, y2) = (a.y.min(b.y), a.y.max(b.y));
+        self.edges().any(|(p, q)| {
+            let (ex1, ex2) = (p.x.min(q.x), p.x.max(q.x));
+            let (ey1, ey2) = (p.y.min(q.y), p.y.max(q.y));
+            [-1, 1].into_iter().any(|d| {
+                // The tiles one step off the edge, clipped to the rectangle
+                let (from, to) = if ex1 == ex2 {
+                    let x = ex1 + d;
+                    (Point { x, y: ey1.max(y1) }, Point { x, y: ey2.min(y2) })
+                } else {
+                    let y = ey1 + d;
+                    (Point { x: ex1.max(x1), y }, Point { x: ex2.min(x2), y })
+                };
+                let clipped = x1 <= from.x && to.x <= x2 && y1 <= from.y && to.y <= y2;
+                clipped && from.x <= to.x && from.y <= to.y && !self.contains_segment(&from, &to)
+            })
+        })
+    }
 
-            let mut hits = 0;
-            let mut prev = '.';
+    // One tile inside and no way out means every tile is inside
+    fn contains_rectangle(&self, a: &Point, b: &Point) -> bool {
+        self.contains(2 * a.x, 2 * a.y) && !self.reaches_outside(a, b)
+    }
 
-            for i in (0..=x).rev() {
-                let cur = grid[y][i];
-                if cur != prev {
-                    hits += 1;
-                }
-                prev = cur;
+    // Cut the segment wherever it meets an edge. Each piece is then wholly in or
+    // out, so checking the cuts and one tile inside every piece is enough.
+    // A piece with no tile strictly inside it holds nothing to check
+    fn contains_segment(&self, a: &Point, b: &Point) -> bool {
+        let horizontal = a.y == b.y;
+        let along = |p: &Point| if horizontal { p.x } else { p.y };
+        let (lo, hi) = (along(a).min(along(b)), along(a).max(along(b)));
+
+        let mut cuts = vec![lo, hi];
+        for (p, q) in self.edges() {
+            if segments_touch(*a, *b, p, q) {
+                // Crossing edges give one point, overlapping ones their clipped ends
+                cuts.push(along(&p).clamp(lo, hi));
+                cuts.push(along(&q).clamp(lo, hi));
             }
+        }
+        cuts.sort();
+        cuts.dedup();
+
+        let inside = |t: i64| {
+            if horizontal {
+                self.contains(2 * t, 2 * a.y)
+            } else {
+                self.contains(2 * a.x, 2 * t)
+            }
+        };
+        cuts.iter().all(|&t| inside(t))
+            && cuts.windows(2).all(|w| w[1] - w[0] < 2 || inside(w[0] + 1))
+    }
+}
 
-            if hits % 2 == 1 {
-                return Point {
-                    x: x as i64,
-                    y: y as i64,
-                };
+fn unique_sorted(values: impl Iterator<Item = i64>) -> Vec<i64> {
+    let mut uniq: Vec<i64> = values.collect();
+    uniq.sort();
+    uniq.dedup();
+    uniq
+}
+
+// Compressed axis: cell 2i is the value uniq[i], cell 2i + 1 the gap up to uniq[i + 1].
+// Each cell gets one tile to stand in for it, or None for a gap with no tiles in it
+fn cell_samples(uniq: &[i64]) -> Vec<Option<i64>> {
+    let mut samples = vec![];
+    for (i, &value) in uniq.iter().enumerate() {
+        samples.push(Some(value));
+        if let Some(&next) = uniq.get(i + 1) {
+            samples.push((next - value > 1).then_some(value + 1));
+        }
+    }
+    samples
+}
+
+fn solve_p2(polygon: &Polygon, options: &RectangleOptions) -> Vec<Rectangle> {
+    let points = &polygon.vertices;
+
+    // Coordinate compression
+    let uniq_x = unique_sorted(points.iter().map(|p| p.x));
+    let uniq_y = unique_sorted(points.iter().map(|p| p.y));
+
+    let x_map: HashMap<i64, usize> = uniq_x
+        .iter()
+        .enumerate()
+        .map(|(i, &x)| (x, 2 * i))
+        .collect();
+    let y_map: HashMap<i64, usize> = uniq_y
+        .iter()
+        .enumerate()
+        .map(|(i, &y)| (y, 2 * i))
+        .collect();
+
+    // Classify every cell, one row at a time
+    let xs = cell_samples(&uniq_x);
+    let grid: Vec<Vec<char>> = cell_samples(&uniq_y)
+        .iter()
+        .map(|&y| classify_row(polygon, y, &xs))
+        .collect();
+
+    println!("Compressed grid:");
+    print_grid(&grid, 20);
+
+    // Candidates come biggest first, so the first enclosed ones win
+    let outside = PrefixSum::new(&grid, '.');
+    candidate_rectangles(points, options)
+        .into_iter()
+        .filter(|r| is_enclosed(&r.a, &r.b, &outside, &x_map, &y_map))
+        .take(options.top_k)
+        .collect()
+}
+
+// One row of the compressed grid: # boundary, X inside, . outside, blank for empty gaps.
+// Same rules as Polygon::contains, but only one pass over the edges per row
+fn classify_row(polygon: &Polygon, y: Option<i64>, xs: &[Option<i64>]) -> Vec<char> {
+    let Some(y) = y else {
+        return vec![' '; xs.len()];
+    };
+    let mut boundary = vec![];
+    let mut crossings = vec![];
+    for (a, b) in polygon.edges() {
+        if a.y.min(b.y) <= y && y <= a.y.max(b.y) {
+            boundary.push((a.x.min(b.x), a.x.max(b.x)));
+        }
+        if a.x == b.x && (a.y <= y) != (b.y <= y) {
+            crossings.push(a.x);
+        }
+    }
+    crossings.sort();
+
+    xs.iter()
+        .map(|&x| match x {
+            None => ' ',
+            Some(x) if boundary.iter().any(|&(lo, hi)| lo <= x && x <= hi) => '#',
+            // Odd number of crossings to the right means inside
+            Some(x) if (crossings.len() - crossings.partition_point(|&c| c <= x)) % 2 == 1 => 'X',
+            Some(_) => '.',
+        })
+        .collect()
+}
+
+// 2D prefix sums counting one kind of cell, so any block can be counted in O(1)
+struct PrefixSum {
+    // sums[y][x] counts matching cells above and left of (x, y), exclusive
+    sums: Vec<Vec<u32>>,
+}
+
+impl PrefixSum {
+    fn new(grid: &[Vec<char>], target: char) -> Self {
+        let width = grid.first().map_or(0, |row| row.len());
+        let mut sums = vec![vec![0; width + 1]; grid.len() + 1];
+        for (y, row) in grid.iter().enumerate() {
+            for (x, &cell) in row.iter().enumerate() {
+                sums[y + 1][x + 1] =
+                    sums[y][x + 1] + sums[y + 1][x] - sums[y][x] + u32::from(cell == target);
             }
         }
+        PrefixSum { sums }
+    }
+
+    // Matching cells in the block x1..=x2, y1..=y2
+    fn count(&self, x1: usize, y1: usize, x2: usize, y2: usize) -> u32 {
+        self.sums[y2 + 1][x2 + 1] + self.sums[y1][x1]
+            - self.sums[y1][x2 + 1]
+            - self.sums[y2 + 1][x1]
     }
-    panic!("Could not find inside point");
 }
 
 fn is_enclosed(
     a: &Point,
     b: &Point,
-    grid: &Vec<Vec<char>>,
+    outside: &PrefixSum,
     x_map: &HashMap<i64, usize>,
     y_map: &HashMap<i64, usize>,
 ) -> bool {
@@ -196,45 +523,406 @@ fn is_enclosed(
     let y1 = y_map[&a.y.min(b.y)];
     let y2 = y_map[&a.y.max(b.y)];
 
-    // Check top and bottom edges
-    for x in x1..=x2 {
-        if grid[y1][x] == '.' || grid[y2][x] == '.' {
-            return false;
-        }
-    }
-
-    // Check left and right edges
-    for y in y1..=y2 {
-        if grid[y][x1] == '.' || grid[y][x2] == '.' {
-            return false;
-        }
-    }
-    true
+    // No outside cell anywhere in the rectangle
+    outside.count(x1, y1, x2, y2) == 0
 }
 
-fn print_grid(grid: &Vec<Vec<char>>, max_rows: usize) {
+fn print_grid(grid: &[Vec<char>], max_rows: usize) {
     let rows = grid.len().min(max_rows);
-    for i in 0..rows {
-        println!("{}", grid[i].iter().collect::<String>());
+    for row in &grid[..rows] {
+        println!("{}", row.iter().collect::<String>());
     }
     if grid.len() > max_rows {
         println!("... ({} more rows)", grid.len() - max_rows);
     }
 }
 
+// SVG in real coordinates: the polygon, the given rectangles (covering whole tiles)
+// and optionally the lines the compressed grid is cut along
+fn render_svg(polygon: &Polygon, rectangles: &[(Rectangle, &str)], grid_lines: bool) -> String {
+    let xs = unique_sorted(polygon.vertices.iter().map(|p| p.x));
+    let ys = unique_sorted(polygon.vertices.iter().map(|p| p.y));
+    let (min_x, max_x) = (xs[0], xs[xs.len() - 1]);
+    let (min_y, max_y) = (ys[0], ys[ys.len() - 1]);
+    let margin = ((max_x - min_x).max(max_y - min_y) / 50).max(1);
+
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"1000\" height=\"1000\">\n",
+        min_x - margin,
+        min_y - margin,
+        max_x - min_x + 2 * margin,
+        max_y - min_y + 2 * margin
+    );
+    // Every shape uses non-scaling-stroke so lines stay thin however big the coordinates get
+    out.push_str("<g fill=\"none\">\n");
+
+    if grid_lines {
+        for &x in &xs {
+            out.push_str(&format!(
+                "<line x1=\"{x}\" y1=\"{}\" x2=\"{x}\" y2=\"{}\" stroke=\"#ddd\" vector-effect=\"non-scaling-stroke\"/>\n",
+                min_y, max_y
+            ));
+        }
+        for &y in &ys {
+            out.push_str(&format!(
+                "<line x1=\"{}\" y1=\"{y}\" x2=\"{}\" y2=\"{y}\" stroke=\"#ddd\" vector-effect=\"non-scaling-stroke\"/>\n",
+                min_x, max_x
+            ));
+        }
+    }
+
+    // Tiles are drawn as unit squares, so the outline and the rectangles both
+    // run along the outer edges of their tiles
+    let path: Vec<String> = polygon
+        .outline()
+        .iter()
+        .map(|(x, y)| format!("{x},{y}"))
+        .collect();
+    out.push_str(&format!(
+        "<polygon points=\"{}\" fill=\"#2e7d32\" fill-opacity=\"0.3\" stroke=\"#c62828\" vector-effect=\"non-scaling-stroke\"/>\n",
+        path.join(" ")
+    ));
+
+    for (Rectangle { a, b, .. }, color) in rectangles {
+        out.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" stroke=\"{}\" stroke-width=\"2\" vector-effect=\"non-scaling-stroke\"/>\n",
+            a.x.min(b.x) as f64 - 0.5,
+            a.y.min(b.y) as f64 - 0.5,
+            (a.x - b.x).abs() + 1,
+            (a.y - b.y).abs() + 1,
+            color
+        ));
+    }
+    out.push_str("</g>\n</svg>\n");
+    out
+}
+
+// Command line options
+struct Options {
+    svg_path: Option<String>,
+    svg_grid: bool,
+    rectangles: RectangleOptions,
+}
+
+fn parse_args() -> Options {
+    let mut options = Options {
+        svg_path: None,
+        svg_grid: false,
+        rectangles: RectangleOptions::default(),
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| panic!("{} needs a value", arg))
+        };
+        match arg.as_str() {
+            "--svg" => options.svg_path = Some(value()),
+            "--svg-grid" => options.svg_grid = true,
+            "--top" => options.rectangles.top_k = value().parse().expect("Invalid --top count"),
+            "--degenerate" => options.rectangles.allow_degenerate = true,
+            "--min-aspect" => {
+                options.rectangles.min_aspect = value().parse().expect("Invalid --min-aspect ratio")
+            }
+            "--contains" => {
+                let point = parse_points(&value());
+                options.rectangles.must_contain =
+                    Some(*point.first().expect("--contains needs a point like x,y"));
+            }
+            other => panic!("Unknown option '{}'", other),
+        }
+    }
+    options
+}
+
 fn main() {
+    let options = parse_args();
     let input = read_input();
     let points = parse_points(&input);
 
     // Part 1
     println!("=== Part 1 ===");
-    let (p1, p2, area) = find_max_rectangle(&points);
-    println!("Point 1: ({}, {})", p1.x, p1.y);
-    println!("Point 2: ({}, {})", p2.x, p2.y);
-    println!("Area: {}", area);
+    let part1 = find_max_rectangles(&points, &options.rectangles);
+    print_rectangles(&part1);
 
     // Part 2
     println!("=== Part 2 ===");
-    let area_p2 = solve_p2(&points);
-    println!("Area: {}", area_p2);
+    let polygon = Polygon::new(&points).unwrap_or_else(|issues| {
+        for issue in issues {
+            eprintln!("Error: {}", issue);
+        }
+        std::process::exit(1);
+    });
+    println!(
+        "Polygon: {} vertices, {:?}, area {}",
+        polygon.vertices.len(),
+        polygon.orientation(),
+        polygon.doubled_area().abs() as f64 / 2.0
+    );
+    let part2 = solve_p2(&polygon, &options.rectangles);
+    print_rectangles(&part2);
+    if let Some(best) = part2.first() {
+        // Double check against the polygon itself
+        println!(
+            "Geometric check: {}",
+            if polygon.contains_rectangle(&best.a, &best.b) {
+                "inside"
+            } else {
+                "NOT inside"
+            }
+        );
+    }
+
+    // Picture of both answers
+    if let Some(path) = &options.svg_path {
+        let mut rectangles = vec![];
+        if let Some(&best) = part1.first() {
+            rectangles.push((best, "#1565c0"));
+        }
+        if let Some(&best) = part2.first() {
+            rectangles.push((best, "#f9a825"));
+        }
+        std::fs::write(path, render_svg(&polygon, &rectangles, options.svg_grid))
+            .expect("Could not write SVG file");
+        println!("Wrote {}", path);
+    }
+}
+
+// The winner in full, then any runners-up on one line each
+fn print_rectangles(rectangles: &[Rectangle]) {
+    let Some(best) = rectangles.first() else {
+        println!("No rectangle fits");
+        return;
+    };
+    println!("Point 1: ({}, {})", best.a.x, best.a.y);
+    println!("Point 2: ({}, {})", best.b.x, best.b.y);
+    println!("Area: {}", best.area);
+    for (rank, r) in rectangles.iter().enumerate().skip(1) {
+        println!(
+            "  #{}: ({}, {}) - ({}, {}), area {}",
+            rank + 1,
+            r.a.x,
+            r.a.y,
+            r.b.x,
+            r.b.y,
+            r.area
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "7,1\n11,1\n11,7\n9,7\n9,5\n2,5\n2,3\n7,3\n";
+
+    #[test]
+    fn test_example() {
+        let points = parse_points(EXAMPLE);
+        let options = RectangleOptions::default();
+        assert_eq!(find_max_rectangles(&points, &options)[0].area, 50);
+        let polygon = Polygon::new(&points).unwrap();
+        let best = solve_p2(&polygon, &options)[0];
+        assert_eq!(best.area, 24);
+        assert!(polygon.contains_rectangle(&best.a, &best.b));
+    }
+
+    #[test]
+    fn test_rectangle_options() {
+        let points = parse_points(EXAMPLE);
+        let polygon = Polygon::new(&points).unwrap();
+        let areas = |rects: Vec<Rectangle>| rects.iter().map(|r| r.area).collect::<Vec<_>>();
+
+        let top = RectangleOptions {
+            top_k: 3,
+            ..Default::default()
+        };
+        assert_eq!(areas(find_max_rectangles(&points, &top)), [50, 50, 40]);
+        assert_eq!(areas(solve_p2(&polygon, &top)), [24, 21, 18]);
+
+        // Lines of tiles count too, so every pair shows up
+        let lines = RectangleOptions {
+            allow_degenerate: true,
+            min_aspect: 0.0,
+            top_k: 100,
+            must_contain: None,
+        };
+        let all = find_max_rectangles(&points, &lines);
+        assert_eq!(all.len(), 28);
+        assert!(all.iter().any(|r| r.is_degenerate()));
+
+        // Squares only
+        let square = RectangleOptions {
+            min_aspect: 1.0,
+            top_k: 100,
+            ..Default::default()
+        };
+        for r in find_max_rectangles(&points, &square) {
+            assert_eq!((r.a.x - r.b.x).abs(), (r.a.y - r.b.y).abs());
+        }
+
+        // Must cover the tile at 10,2
+        let covering = RectangleOptions {
+            must_contain: Some(Point { x: 10, y: 2 }),
+            top_k: 100,
+            ..Default::default()
+        };
+        let enclosed = solve_p2(&polygon, &covering);
+        assert!(!enclosed.is_empty());
+        assert!(enclosed.iter().all(|r| r.contains(&Point { x: 10, y: 2 })));
+    }
+
+    #[test]
+    fn test_polygon_geometry() {
+        let polygon = Polygon::new(&parse_points(EXAMPLE)).unwrap();
+        assert_eq!(polygon.doubled_area().abs(), 2 * 30);
+        assert_eq!(polygon.orientation(), Orientation::CounterClockwise);
+
+        // Doubled coordinates: corner, edge, inside, the notch, far away
+        assert!(polygon.contains(14, 2));
+        assert!(polygon.contains(22, 7));
+        assert!(polygon.contains(19, 9));
+        assert!(!polygon.contains(6, 4));
+        assert!(!polygon.contains(30, 30));
+
+        // Spanning the notch at the bottom left is not allowed
+        let p = |x, y| Point { x, y };
+        assert!(polygon.contains_rectangle(&p(2, 3), &p(9, 5)));
+        assert!(!polygon.contains_rectangle(&p(2, 3), &p(11, 7)));
+        assert!(polygon.reaches_outside(&p(2, 3), &p(11, 7)));
+    }
+
+    #[test]
+    fn test_wide_outside_gap() {
+        // A U shape: the gap between the arms is outside even though
+        // no vertex sits in it
+        let points = parse_points("0,0\n2,0\n2,8\n8,8\n8,0\n10,0\n10,10\n0,10\n");
+        let polygon = Polygon::new(&points).unwrap();
+        let best = solve_p2(&polygon, &RectangleOptions::default())[0];
+        assert_eq!(best.area, 33); // 11 x 3 along the bottom
+        assert!(!polygon.contains_rectangle(&points[0], &points[6]));
+    }
+
+    #[test]
+    fn test_degenerate_rectangles() {
+        // A W: two notches reach up from the bottom edge
+        let polygon = Polygon::new(&parse_points(
+            "0,0\n2,0\n2,6\n4,6\n4,0\n6,0\n6,6\n8,6\n8,0\n10,0\n10,10\n0,10\n",
+        ))
+        .unwrap();
+        let p = |x, y| Point { x, y };
+
+        // Along the bottom the line dips out through both notches,
+        // even though both ends and the middle are on the boundary
+        assert!(!polygon.contains_rectangle(&p(0, 0), &p(10, 0)));
+        assert!(!polygon.contains_rectangle(&p(3, 0), &p(3, 10)));
+        // Across the tops of the notches and along the top edge stays inside
+        assert!(polygon.contains_rectangle(&p(2, 6), &p(8, 6)));
+        assert!(polygon.contains_rectangle(&p(0, 10), &p(10, 10)));
+        assert!(polygon.contains_rectangle(&p(5, 0), &p(5, 10)));
+        // Single tiles
+        assert!(polygon.contains_rectangle(&p(4, 0), &p(4, 0)));
+        assert!(!polygon.contains_rectangle(&p(3, 2), &p(3, 2)));
+
+        // The grid search agrees on which lines fit
+        let options = RectangleOptions {
+            allow_degenerate: true,
+            top_k: 100,
+            ..Default::default()
+        };
+        for r in solve_p2(&polygon, &options) {
+            assert!(polygon.contains_rectangle(&r.a, &r.b), "{:?}", r);
+        }
+    }
+
+    #[test]
+    fn test_one_wide_notch() {
+        // The notch between x=3 and x=4 is outside, but has no tile in it:
+        // every tile around it is on the boundary
+        let points = parse_points("5,5\n1,5\n1,4\n2,4\n2,2\n3,2\n3,3\n4,3\n4,2\n5,2\n");
+        let polygon = Polygon::new(&points).unwrap();
+        let best = solve_p2(&polygon, &RectangleOptions::default())[0];
+        assert_eq!(best.area, 16);
+        assert!(polygon.contains_rectangle(&best.a, &best.b));
+        let p = |x, y| Point { x, y };
+        assert!(polygon.contains_rectangle(&p(2, 2), &p(5, 2)));
+
+        // Every rectangle agrees with checking it tile by tile
+        for a in &points {
+            for b in &points {
+                let tiles = (a.x.min(b.x)..=a.x.max(b.x))
+                    .all(|x| (a.y.min(b.y)..=a.y.max(b.y)).all(|y| polygon.contains(2 * x, 2 * y)));
+                assert_eq!(polygon.contains_rectangle(a, b), tiles, "{:?} {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_degenerate_polygons() {
+        let check = |input: &str| Polygon::new(&parse_points(input)).err().unwrap();
+        assert_eq!(check("0,0\n5,0\n5,5\n"), [PolygonIssue::TooFewVertices(3)]);
+        assert_eq!(
+            check("0,0\n5,0\n5,0\n5,5\n0,5\n"),
+            [PolygonIssue::ZeroLengthEdge(1, 2)]
+        );
+        assert!(check("0,0\n5,0\n2,0\n2,5\n0,5\n").contains(&PolygonIssue::Backtrack(1)));
+        assert_eq!(
+            check("0,0\n5,0\n5,0\n0,0\n").last(),
+            Some(&PolygonIssue::ZeroArea)
+        );
+    }
+
+    #[test]
+    fn test_polygon_validation() {
+        let check = |input: &str| Polygon::new(&parse_points(input)).err().unwrap();
+
+        // Closing vertex written out again
+        assert_eq!(
+            check("0,0\n5,0\n5,5\n0,5\n0,0\n"),
+            [PolygonIssue::DuplicateClosingVertex(4)]
+        );
+        // Diagonal edge, including the one closing the loop
+        assert_eq!(
+            check("0,0\n5,0\n5,5\n1,5\n"),
+            [PolygonIssue::DiagonalEdge(3, 0)]
+        );
+        // Figure of eight: edges 1-2 and 4-5 cross at 4,2
+        let issues = check("0,0\n4,0\n4,4\n6,4\n6,2\n2,2\n2,6\n0,6\n");
+        assert_eq!(issues, [PolygonIssue::SelfIntersection((1, 2), (4, 5))]);
+        assert_eq!(issues[0].to_string(), "edge 1-2 touches edge 4-5");
+
+        assert!(validate_polygon(&parse_points(EXAMPLE)).is_empty());
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let polygon = Polygon::new(&parse_points(EXAMPLE)).unwrap();
+        let rect = Rectangle::new(Point { x: 2, y: 3 }, Point { x: 9, y: 5 });
+        let svg = render_svg(&polygon, &[(rect, "red")], true);
+        assert!(svg.contains(
+            "points=\"6.5,0.5 11.5,0.5 11.5,7.5 8.5,7.5 8.5,5.5 1.5,5.5 1.5,2.5 6.5,2.5\""
+        ));
+        assert!(svg.contains("<rect x=\"1.5\" y=\"2.5\" width=\"8\" height=\"3\""));
+        // Going round the other way pushes the same corners out
+        let mut reversed = parse_points(EXAMPLE);
+        reversed.reverse();
+        let mut corners = Polygon::new(&reversed).unwrap().outline();
+        corners.reverse();
+        assert_eq!(corners, polygon.outline());
+        // Four distinct x and four distinct y values
+        assert_eq!(svg.matches("<line").count(), 8);
+        assert_eq!(render_svg(&polygon, &[], false).matches("<line").count(), 0);
+    }
+
+    #[test]
+    fn test_prefix_sum() {
+        let grid: Vec<Vec<char>> = ["#..", ".#.", "..#"]
+            .iter()
+            .map(|row| row.chars().collect())
+            .collect();
+        let dots = PrefixSum::new(&grid, '.');
+        assert_eq!(dots.count(0, 0, 2, 2), 6);
+        assert_eq!(dots.count(1, 1, 1, 1), 0);
+        assert_eq!(dots.count(1, 0, 2, 1), 3);
+    }
 }
@@ -225,7 +225,7 @@ fn solve_p2_recurse(
 fn solve_p2(device: &Device) -> usize {
     let targets = device.joltage_targets.as_ref().unwrap();
     let mut cache = HashMap::new();
-    solve_p2_recurse(&device.buttons, &targets, &mut cache)
+    solve_p2_recurse(&device.buttons, targets, &mut cache)
 }
 
 fn main() {
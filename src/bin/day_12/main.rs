@@ -135,6 +135,7 @@ fn solve(
     false
 }
 
+#[allow(dead_code)] // Debug helper
 fn print_grid(grid: &[Vec<Option<char>>]) {
     for row in grid {
         for cell in row {
@@ -157,10 +158,8 @@ fn main() {
     }
 
     // Generate all transformations
-    let all_transformations: Vec<Vec<Shape>> = base_gifts
-        .iter()
-        .map(|g| generate_transformations(g))
-        .collect();
+    let all_transformations: Vec<Vec<Shape>> =
+        base_gifts.iter().map(generate_transformations).collect();
 
     // Process all trees
     let mut valid_count = 0;
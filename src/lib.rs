@@ -0,0 +1,2 @@
+// Helpers shared between days
+pub mod union_find;
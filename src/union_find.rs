@@ -0,0 +1,206 @@
+// Union-Find (disjoint set union) with union by size
+//
+// By default find compresses paths. A structure built with `with_rollback`
+// skips compression instead, so every union can be undone in reverse order.
+pub struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+    components: usize,
+    // Roots attached by each union, only kept when rollback is enabled
+    history: Option<Vec<usize>>,
+}
+
+impl UnionFind {
+    pub fn new(n: usize) -> Self {
+        UnionFind {
+            parent: (0..n).collect(),
+            size: vec![1; n],
+            components: n,
+            history: None,
+        }
+    }
+
+    // Unions can be undone with `snapshot` and `rollback`
+    pub fn with_rollback(n: usize) -> Self {
+        UnionFind {
+            history: Some(vec![]),
+            ..UnionFind::new(n)
+        }
+    }
+
+    // Number of elements
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    // Find the root of the set containing x, with a loop rather than recursion
+    // (union by size already keeps trees O(log n) deep)
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        // Path compression, skipped when unions have to stay undoable
+        if self.history.is_none() {
+            let mut node = x;
+            while self.parent[node] != root {
+                let next = self.parent[node];
+                self.parent[node] = root;
+                node = next;
+            }
+        }
+        root
+    }
+
+    // Unite two sets, returns true if they were separate
+    pub fn union(&mut self, x: usize, y: usize) -> bool {
+        let mut root_x = self.find(x);
+        let mut root_y = self.find(y);
+
+        if root_x == root_y {
+            return false; // already in same set
+        }
+
+        // Union by size (attach smaller tree to larger)
+        if self.size[root_x] < self.size[root_y] {
+            std::mem::swap(&mut root_x, &mut root_y);
+        }
+        self.parent[root_y] = root_x;
+        self.size[root_x] += self.size[root_y];
+        self.components -= 1;
+
+        if let Some(history) = &mut self.history {
+            history.push(root_y);
+        }
+        true
+    }
+
+    pub fn connected(&mut self, x: usize, y: usize) -> bool {
+        self.find(x) == self.find(y)
+    }
+
+    // Number of separate sets
+    pub fn component_count(&self) -> usize {
+        self.components
+    }
+
+    // Size of the set containing x
+    pub fn size_of(&mut self, x: usize) -> usize {
+        let root = self.find(x);
+        self.size[root]
+    }
+
+    // Every element in the same set as x, in increasing order
+    pub fn members_of(&mut self, x: usize) -> Vec<usize> {
+        let root = self.find(x);
+        (0..self.len()).filter(|&i| self.find(i) == root).collect()
+    }
+
+    // All sets, each in increasing order, ordered by their smallest element
+    pub fn components(&mut self) -> Vec<Vec<usize>> {
+        let mut slot = vec![usize::MAX; self.len()];
+        let mut groups: Vec<Vec<usize>> = vec![];
+        for i in 0..self.len() {
+            let root = self.find(i);
+            if slot[root] == usize::MAX {
+                slot[root] = groups.len();
+                groups.push(vec![]);
+            }
+            groups[slot[root]].push(i);
+        }
+        groups
+    }
+
+    // Size of every set, one entry per root
+    pub fn component_sizes(&self) -> Vec<usize> {
+        (0..self.len())
+            .filter(|&i| self.parent[i] == i)
+            .map(|i| self.size[i])
+            .collect()
+    }
+
+    // Marker for the current state, to hand back to `rollback`
+    pub fn snapshot(&self) -> usize {
+        self.history
+            .as_ref()
+            .expect("UnionFind was not built with_rollback")
+            .len()
+    }
+
+    // Undo every union made since the snapshot was taken
+    pub fn rollback(&mut self, snapshot: usize) {
+        let history = self
+            .history
+            .as_mut()
+            .expect("UnionFind was not built with_rollback");
+        while history.len() > snapshot {
+            let child = history.pop().unwrap();
+            let root = self.parent[child];
+            self.size[root] -= self.size[child];
+            self.parent[child] = child;
+            self.components += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_union_and_components() {
+        let mut uf = UnionFind::new(6);
+        assert!(uf.union(0, 1));
+        assert!(uf.union(1, 2));
+        assert!(!uf.union(0, 2));
+        assert!(uf.union(4, 5));
+
+        assert_eq!(uf.component_count(), 3);
+        assert_eq!(uf.size_of(2), 3);
+        assert_eq!(uf.members_of(1), vec![0, 1, 2]);
+        assert_eq!(uf.components(), vec![vec![0, 1, 2], vec![3], vec![4, 5]]);
+
+        let mut sizes = uf.component_sizes();
+        sizes.sort();
+        assert_eq!(sizes, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_long_chain_find() {
+        // Union by size never builds a deep tree, so wire up a chain
+        // 0 -> 1 -> ... -> n - 1 by hand
+        let n = 100_000;
+        let mut uf = UnionFind::new(n);
+        for i in 0..n - 1 {
+            uf.parent[i] = i + 1;
+        }
+        uf.size[n - 1] = n;
+        uf.components = 1;
+
+        assert_eq!(uf.find(0), n - 1);
+        // The whole path now points straight at the root
+        assert!((0..n).all(|i| uf.parent[i] == n - 1));
+        assert!(uf.connected(0, n / 2));
+    }
+
+    #[test]
+    fn test_rollback() {
+        let mut uf = UnionFind::with_rollback(5);
+        uf.union(0, 1);
+        let snapshot = uf.snapshot();
+        uf.union(1, 2);
+        uf.union(3, 4);
+        assert_eq!(uf.component_count(), 2);
+
+        uf.rollback(snapshot);
+        assert_eq!(uf.component_count(), 4);
+        assert_eq!(uf.size_of(0), 2);
+        assert!(!uf.connected(1, 2));
+        assert!(!uf.connected(3, 4));
+    }
+}